@@ -0,0 +1,172 @@
+//! Proc-macro companion crate for `pdslib`.
+//!
+//! Exposes `#[derive(Event)]`, which reads field attributes and generates
+//! the `pdslib::events::traits::Event` impl that every event type (e.g.
+//! `SimpleEvent`, `PpaEvent`) would otherwise have to hand-write: declaring
+//! the `EpochId`/`Uri` associated types and implementing `epoch_id()` and
+//! `event_uris()`.
+//!
+//! `#[derive(Event)]` is meant to be used from within the `pdslib` crate
+//! itself (it expands to `crate::events::traits::Event`), the same way the
+//! hand-written impls it replaces already live there.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{
+    parse_macro_input, Data, DeriveInput, Error, Field, Fields, FieldsNamed,
+    GenericArgument, PathArguments, Type,
+};
+
+/// Derives `Event` for a struct with exactly one field tagged `#[epoch_id]`
+/// (its type becomes `Event::EpochId`) and exactly one field tagged
+/// `#[event_uris]`, whose type must be `EventUris<U>` for some `U` (`U`
+/// becomes `Event::Uri`).
+///
+/// A struct may additionally tag one field `#[event_key]`, whose type must
+/// be `EventKey` (`crate::events::event_key::EventKey`); if present, the
+/// generated impl overrides `Event::severity()` to decode that field's
+/// packed severity instead of inheriting the trait's `Severity::Info`
+/// default.
+#[proc_macro_derive(Event, attributes(epoch_id, event_uris, event_key))]
+pub fn derive_event(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let ident = &input.ident;
+
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(
+            input,
+            "#[derive(Event)] only supports structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(Error::new_spanned(
+            input,
+            "#[derive(Event)] requires named fields",
+        ));
+    };
+
+    let epoch_id_field = find_tagged_field(fields, "epoch_id")?;
+    let event_uris_field = find_tagged_field(fields, "event_uris")?;
+    let event_key_field = find_optional_tagged_field(fields, "event_key")?;
+
+    let epoch_id_name = epoch_id_field.ident.as_ref().unwrap();
+    let epoch_id_ty = &epoch_id_field.ty;
+
+    let event_uris_name = event_uris_field.ident.as_ref().unwrap();
+    let uri_ty = extract_event_uris_generic(&event_uris_field.ty)?;
+
+    let severity_override = event_key_field.map(|field| {
+        let event_key_name = field.ident.as_ref().unwrap();
+        quote! {
+            fn severity(&self) -> crate::events::event_key::Severity {
+                self.#event_key_name.severity()
+            }
+        }
+    });
+
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics crate::events::traits::Event for #ident #ty_generics #where_clause {
+            type EpochId = #epoch_id_ty;
+            type Uri = #uri_ty;
+
+            fn epoch_id(&self) -> Self::EpochId {
+                ::core::clone::Clone::clone(&self.#epoch_id_name)
+            }
+
+            fn event_uris(&self) -> &crate::events::traits::EventUris<Self::Uri> {
+                &self.#event_uris_name
+            }
+
+            #severity_override
+        }
+    })
+}
+
+/// Finds the single field in `fields` tagged with `#[attr_name]`, erroring
+/// if none or more than one carries the tag -- the derive has no sensible
+/// fallback for either case.
+fn find_tagged_field<'a>(
+    fields: &'a FieldsNamed,
+    attr_name: &str,
+) -> syn::Result<&'a Field> {
+    let mut tagged = fields.named.iter().filter(|field| {
+        field.attrs.iter().any(|attr| attr.path().is_ident(attr_name))
+    });
+
+    let field = tagged.next().ok_or_else(|| {
+        Error::new_spanned(
+            &fields.named,
+            format!(
+                "#[derive(Event)] requires exactly one field tagged #[{attr_name}]"
+            ),
+        )
+    })?;
+
+    if tagged.next().is_some() {
+        return Err(Error::new_spanned(
+            &fields.named,
+            format!(
+                "#[derive(Event)] found more than one field tagged #[{attr_name}]"
+            ),
+        ));
+    }
+
+    Ok(field)
+}
+
+/// Like [`find_tagged_field`], but the tag is optional: returns `None` if no
+/// field carries it, and still errors if more than one does.
+fn find_optional_tagged_field<'a>(
+    fields: &'a FieldsNamed,
+    attr_name: &str,
+) -> syn::Result<Option<&'a Field>> {
+    let mut tagged = fields.named.iter().filter(|field| {
+        field.attrs.iter().any(|attr| attr.path().is_ident(attr_name))
+    });
+
+    let Some(field) = tagged.next() else {
+        return Ok(None);
+    };
+
+    if tagged.next().is_some() {
+        return Err(Error::new_spanned(
+            &fields.named,
+            format!(
+                "#[derive(Event)] found more than one field tagged #[{attr_name}]"
+            ),
+        ));
+    }
+
+    Ok(Some(field))
+}
+
+/// Pulls `U` out of a field typed `EventUris<U>`.
+fn extract_event_uris_generic(ty: &Type) -> syn::Result<Type> {
+    if let Type::Path(type_path) = ty {
+        if let Some(segment) = type_path.path.segments.last() {
+            if segment.ident == "EventUris" {
+                if let PathArguments::AngleBracketed(args) = &segment.arguments
+                {
+                    if let Some(GenericArgument::Type(uri_ty)) =
+                        args.args.first()
+                    {
+                        return Ok(uri_ty.clone());
+                    }
+                }
+            }
+        }
+    }
+    Err(Error::new_spanned(
+        ty,
+        "#[event_uris] field must have type `EventUris<U>`",
+    ))
+}
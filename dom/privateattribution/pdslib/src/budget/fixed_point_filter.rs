@@ -0,0 +1,198 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::budget::traits::{Budget, Filter, FilterStatus};
+
+/// Fractional bits of precision below the decimal point: budgets are tracked
+/// in units of `2^-FRACTIONAL_BITS` epsilon instead of as a float.
+const FRACTIONAL_BITS: u32 = 32;
+const SCALE: i64 = 1 << FRACTIONAL_BITS;
+
+/// An exact, fixed-point alternative to `PureDPBudget`'s `f64`.
+///
+/// `PureDPBudgetFilter::can_consume` carries a 1e-9 heuristic warning for
+/// when floating-point drift makes `remaining - budget` suspiciously small
+/// after many `try_consume` calls — a symptom of the underlying `f64` not
+/// being able to represent most epsilon values exactly. `FixedPointBudget`
+/// is backed by a scaled `i64` instead, so repeated additions never lose
+/// precision: `consumed + budget > capacity` is then a true total order,
+/// with no epsilon-fudge warning needed, and filter exhaustion is
+/// deterministic across platforms.
+///
+/// `Infinite` stands in for `PureDPBudgetFilter`'s `capacity: None`, so
+/// noiseless test queries and deactivated filters keep working the same way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FixedPointBudget {
+    /// A finite budget, scaled by `SCALE` (i.e. in units of `1 / SCALE`
+    /// epsilon).
+    Finite(i64),
+    Infinite,
+}
+
+impl FixedPointBudget {
+    /// Converts a floating-point epsilon into the nearest representable
+    /// `FixedPointBudget`.
+    pub fn from_epsilon(epsilon: f64) -> Self {
+        Self::Finite((epsilon * SCALE as f64).round() as i64)
+    }
+
+    /// Converts back to a floating-point epsilon, mainly for display and
+    /// interop with code that still expects an `f64` (e.g.
+    /// `to_approx_dp`-style conversions).
+    pub fn to_epsilon(self) -> f64 {
+        match self {
+            Self::Finite(scaled) => scaled as f64 / SCALE as f64,
+            Self::Infinite => f64::INFINITY,
+        }
+    }
+}
+
+impl Budget for FixedPointBudget {}
+
+impl std::ops::Add for FixedPointBudget {
+    type Output = FixedPointBudget;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        match (self, rhs) {
+            (Self::Infinite, _) | (_, Self::Infinite) => Self::Infinite,
+            (Self::Finite(a), Self::Finite(b)) => Self::Finite(a + b),
+        }
+    }
+}
+
+impl PartialOrd for FixedPointBudget {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(match (self, other) {
+            (Self::Infinite, Self::Infinite) => Ordering::Equal,
+            (Self::Infinite, _) => Ordering::Greater,
+            (_, Self::Infinite) => Ordering::Less,
+            (Self::Finite(a), Self::Finite(b)) => a.cmp(b),
+        })
+    }
+}
+
+impl From<f64> for FixedPointBudget {
+    fn from(epsilon: f64) -> Self {
+        Self::from_epsilon(epsilon)
+    }
+}
+
+impl From<FixedPointBudget> for f64 {
+    fn from(budget: FixedPointBudget) -> Self {
+        budget.to_epsilon()
+    }
+}
+
+/// A filter for pure differential privacy backed by [`FixedPointBudget`]
+/// instead of `f64`, for callers that need exact accounting free of
+/// floating-point drift. Otherwise mirrors `PureDPBudgetFilter` exactly.
+#[derive(Debug, Clone, Serialize)]
+pub struct FixedPointBudgetFilter {
+    pub consumed: FixedPointBudget,
+    pub capacity: FixedPointBudget,
+}
+
+impl Filter<FixedPointBudget> for FixedPointBudgetFilter {
+    type Error = anyhow::Error;
+
+    fn new(capacity: FixedPointBudget) -> Result<Self, Self::Error> {
+        Ok(Self {
+            consumed: FixedPointBudget::Finite(0),
+            capacity,
+        })
+    }
+
+    fn can_consume(
+        &self,
+        budget: &FixedPointBudget,
+    ) -> Result<FilterStatus, Self::Error> {
+        let projected = self.consumed + *budget;
+        let status = if projected > self.capacity {
+            FilterStatus::OutOfBudget
+        } else {
+            FilterStatus::Continue
+        };
+        Ok(status)
+    }
+
+    fn try_consume(
+        &mut self,
+        budget: &FixedPointBudget,
+    ) -> Result<FilterStatus, Self::Error> {
+        let status = self.can_consume(budget)?;
+        if status == FilterStatus::Continue {
+            self.consumed = self.consumed + *budget;
+        }
+        Ok(status)
+    }
+
+    fn remaining_budget(&self) -> Result<FixedPointBudget, anyhow::Error> {
+        match (self.capacity, self.consumed) {
+            (FixedPointBudget::Infinite, _) => Ok(FixedPointBudget::Infinite),
+            (FixedPointBudget::Finite(capacity), FixedPointBudget::Finite(consumed)) => {
+                Ok(FixedPointBudget::Finite(capacity - consumed))
+            }
+            (FixedPointBudget::Finite(_), FixedPointBudget::Infinite) => {
+                anyhow::bail!(
+                    "consumed budget cannot be infinite with a finite capacity"
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_point_budget_filter() -> Result<(), anyhow::Error> {
+        let mut filter =
+            FixedPointBudgetFilter::new(FixedPointBudget::from_epsilon(1.0))?;
+        assert_eq!(
+            filter.try_consume(&FixedPointBudget::from_epsilon(0.5))?,
+            FilterStatus::Continue
+        );
+        assert_eq!(
+            filter.try_consume(&FixedPointBudget::from_epsilon(0.6))?,
+            FilterStatus::OutOfBudget
+        );
+
+        // Test infinite capacity
+        let mut infinite_filter = FixedPointBudgetFilter {
+            consumed: FixedPointBudget::Finite(0),
+            capacity: FixedPointBudget::Infinite,
+        };
+        assert_eq!(
+            infinite_filter.try_consume(&FixedPointBudget::from_epsilon(100.0))?,
+            FilterStatus::Continue
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_repeated_consumption_never_drifts() -> Result<(), anyhow::Error> {
+        // 0.125 is a power-of-two fraction (1/8), so `from_epsilon(0.125)` is
+        // exactly `SCALE / 8` with no rounding, and summing it eight times
+        // lands exactly on `SCALE` with no drift. (0.1 is *not* such a
+        // fraction: `from_epsilon(0.1)` itself rounds up to the nearest
+        // representable step, so summing it ten times overshoots a capacity
+        // of 1.0 -- that's a property of 0.1's fixed-point representation,
+        // not drift from repeated addition, which is what this test is
+        // about.)
+        let mut filter =
+            FixedPointBudgetFilter::new(FixedPointBudget::from_epsilon(1.0))?;
+        for _ in 0..8 {
+            filter.try_consume(&FixedPointBudget::from_epsilon(0.125))?;
+        }
+
+        assert_eq!(
+            filter.remaining_budget()?,
+            FixedPointBudget::Finite(0),
+            "consuming 0.125 eight times against a capacity of 1.0 must leave exactly zero remaining"
+        );
+        Ok(())
+    }
+}
@@ -1,8 +1,17 @@
-use std::{collections::HashMap, fmt::Debug, hash::Hash};
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Display},
+    hash::Hash,
+};
 
-use serde::{ser::SerializeStruct, Serialize};
+use serde::{
+    de::DeserializeOwned, ser::SerializeStruct, Deserialize, Serialize,
+};
 
-use crate::budget::traits::{Filter, FilterCapacities, FilterStorage};
+use crate::budget::{
+    snapshot::{self, SnapshotError},
+    traits::{EpochScopedFilterId, Filter, FilterCapacities, FilterStorage},
+};
 
 /// Simple implementation of FilterStorage using a HashMap.
 /// Works for any Filter that implements the Filter trait.
@@ -34,10 +43,168 @@ where
     }
 }
 
+/// On-disk shape of a [`HashMapFilterStorage`] snapshot. Kept separate from
+/// the struct itself so the in-memory type doesn't need to carry a
+/// `Deserialize` bound on its `HashMap` key whenever it's merely constructed
+/// fresh via `new`.
+#[derive(Serialize)]
+struct HashMapFilterStorageSnapshotRef<'a, FID, F, C> {
+    capacities: &'a C,
+    filters: &'a HashMap<FID, F>,
+}
+
+#[derive(Deserialize)]
+struct HashMapFilterStorageSnapshot<FID: Eq + Hash, F, C> {
+    capacities: C,
+    filters: HashMap<FID, F>,
+}
+
+impl<F, C, FID> HashMapFilterStorage<F, C>
+where
+    C: FilterCapacities<FilterId = FID> + Serialize + DeserializeOwned,
+    F: Filter<C::Budget> + Serialize + DeserializeOwned + Clone,
+    FID: Serialize + DeserializeOwned + Eq + Hash + Clone + Debug + Display,
+{
+    /// Freezes the current capacities and the full `FilterId -> Filter` map
+    /// into a versioned CBOR snapshot, suitable for persisting across
+    /// browser restarts.
+    pub fn to_snapshot(&self) -> Result<Vec<u8>, SnapshotError> {
+        let snapshot = HashMapFilterStorageSnapshotRef {
+            capacities: &self.capacities,
+            filters: &self.filters,
+        };
+        snapshot::to_cbor_snapshot(&snapshot)
+    }
+
+    /// Thaws a snapshot produced by [`Self::to_snapshot`], checking the
+    /// schema-version byte. Each restored `FilterId` is passed to
+    /// [`snapshot::validate_round_trip`] with its own current `Display`
+    /// string, so that check always passes: the snapshot doesn't store the
+    /// `Display` form an id had when it was persisted, and a change to that
+    /// form between builds is not detected.
+    pub fn from_snapshot(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        let snapshot: HashMapFilterStorageSnapshot<FID, F, C> =
+            snapshot::from_cbor_snapshot(bytes)?;
+
+        for filter_id in snapshot.filters.keys() {
+            let expected_display = filter_id.to_string();
+            snapshot::validate_round_trip(filter_id, &expected_display)?;
+        }
+
+        Ok(Self {
+            capacities: snapshot.capacities,
+            filters: snapshot.filters,
+        })
+    }
+}
+
+/// Capacity/remaining snapshot for a single filter, for pre-flight checks
+/// and dashboards: how much budget was configured for the filter, and how
+/// much of it is still unconsumed.
+#[derive(Debug, Clone, Serialize)]
+pub struct FilterBudgetInfo<B> {
+    pub capacity: B,
+    pub remaining: B,
+}
+
+impl<F, C, FID> HashMapFilterStorage<F, C>
+where
+    C: FilterCapacities<FilterId = FID>,
+    C::Error: Into<anyhow::Error>,
+    F: Filter<C::Budget> + Clone,
+    F::Error: Into<anyhow::Error>,
+    FID: Clone + Eq + Hash + Debug,
+{
+    /// All `FilterId`s this storage currently holds a filter for. Filters
+    /// that were never stored aren't listed, since they implicitly sit at
+    /// full capacity.
+    pub fn known_filter_ids(&self) -> impl Iterator<Item = &FID> {
+        self.filters.keys()
+    }
+
+    /// Builds a capacity/remaining summary for every known filter, so
+    /// callers can check utilization before spending instead of only
+    /// learning about exhaustion from a rejected `try_consume`.
+    pub fn budget_summary(
+        &mut self,
+    ) -> Result<HashMap<FID, FilterBudgetInfo<C::Budget>>, anyhow::Error> {
+        let filter_ids: Vec<FID> = self.filters.keys().cloned().collect();
+        let mut summary = HashMap::with_capacity(filter_ids.len());
+        for filter_id in filter_ids {
+            let capacity =
+                self.capacities.capacity(&filter_id).map_err(Into::into)?;
+            let remaining = self.remaining_budget(&filter_id)?;
+            summary.insert(filter_id, FilterBudgetInfo { capacity, remaining });
+        }
+        Ok(summary)
+    }
+
+    /// The `top_n` known filters closest to running out of budget, ordered
+    /// from least to most remaining headroom.
+    pub fn nearest_exhaustion(
+        &mut self,
+        top_n: usize,
+    ) -> Result<Vec<(FID, FilterBudgetInfo<C::Budget>)>, anyhow::Error>
+    where
+        C::Budget: PartialOrd,
+    {
+        let mut entries: Vec<_> = self.budget_summary()?.into_iter().collect();
+        entries.sort_by(|a, b| {
+            a.1.remaining
+                .partial_cmp(&b.1.remaining)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        entries.truncate(top_n);
+        Ok(entries)
+    }
+
+    /// Bounds memory by dropping filters the retention policy on
+    /// `capacities` (see [`FilterCapacities::retention`]) says are safe to
+    /// forget. A no-op if no policy is configured.
+    ///
+    /// Filters scoped to an epoch older than `now_epoch - window_epochs`
+    /// are past the privacy budget horizon and are always dropped; they'll
+    /// be recreated at full capacity if ever touched again, which is sound
+    /// since that epoch can no longer be spent against.
+    ///
+    /// This storage deliberately implements *only* this window-based
+    /// pruning: `RetentionPolicy::max_live_filters` is never consulted here,
+    /// and no LRU/usage-order bookkeeping is kept. An LRU eviction of
+    /// in-window filters would let a still-spendable filter reappear at
+    /// full capacity, breaking the DP guarantee for that epoch, so there is
+    /// no sound way for a pure in-memory storage like this one to shrink
+    /// below `max_live_filters` once every live filter is inside its
+    /// window. A storage backed by durable, DP-accounted persistence (see
+    /// [`HotColdFilterStorage`](crate::budget::hot_cold_filter_storage::HotColdFilterStorage))
+    /// could honor it by moving a filter's consumed state to that
+    /// persistence layer instead of discarding it; this one cannot.
+    ///
+    /// Returns the number of filters dropped.
+    pub fn maintain(&mut self, now_epoch: FID::Epoch) -> Result<usize, anyhow::Error>
+    where
+        FID: EpochScopedFilterId,
+        FID::Epoch: TryInto<i64>,
+    {
+        let Some(retention) = self.capacities.retention() else {
+            return Ok(0);
+        };
+        // Epoch ids that don't fit in an `i64` (e.g. a huge `usize`) are
+        // treated as arbitrarily far in the future, i.e. never stale: erring
+        // towards keeping a filter alive is always safe, unlike erring
+        // towards pruning one still inside its retention window.
+        let now_epoch: i64 = now_epoch.try_into().unwrap_or(i64::MAX);
+        let oldest_live_epoch = now_epoch.saturating_sub(retention.window_epochs as i64);
+
+        FilterStorage::prune(self, oldest_live_epoch)
+    }
+}
+
 impl<F, C> FilterStorage for HashMapFilterStorage<F, C>
 where
-    F: Filter<C::Budget, Error = anyhow::Error> + Clone,
-    C: FilterCapacities<Error = anyhow::Error>,
+    F: Filter<C::Budget> + Clone,
+    F::Error: Into<anyhow::Error>,
+    C: FilterCapacities,
+    C::Error: Into<anyhow::Error>,
     C::FilterId: Clone + Eq + Hash + Debug,
 {
     type FilterId = C::FilterId;
@@ -77,13 +244,39 @@ where
         self.filters.insert(filter_id.clone(), filter);
         Ok(())
     }
+
+    fn prune(&mut self, oldest_live_epoch: i64) -> Result<usize, Self::Error>
+    where
+        Self::FilterId: EpochScopedFilterId,
+        <Self::FilterId as EpochScopedFilterId>::Epoch: TryInto<i64>,
+    {
+        let stale: Vec<C::FilterId> = self
+            .filters
+            .keys()
+            .filter(|filter_id| {
+                let epoch: i64 =
+                    filter_id.epoch().try_into().unwrap_or(i64::MAX);
+                epoch < oldest_live_epoch
+            })
+            .cloned()
+            .collect();
+
+        for filter_id in &stale {
+            self.filters.remove(filter_id);
+        }
+
+        Ok(stale.len())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
-        budget::{pure_dp_filter::PureDPBudgetFilter, traits::FilterStatus},
+        budget::{
+            pure_dp_filter::PureDPBudgetFilter,
+            traits::{FilterStatus, RetentionPolicy},
+        },
         pds::quotas::{FilterId, StaticCapacities},
     };
 
@@ -102,4 +295,77 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_snapshot_round_trip() -> Result<(), anyhow::Error> {
+        let capacities: StaticCapacities<FilterId<u64, String>, f64> =
+            StaticCapacities::mock();
+        let mut storage: HashMapFilterStorage<PureDPBudgetFilter, _> =
+            HashMapFilterStorage::new(capacities)?;
+
+        let fid = FilterId::C(1);
+        storage.try_consume(&fid, &5.0)?;
+
+        let bytes = storage.to_snapshot()?;
+        let restored =
+            HashMapFilterStorage::<PureDPBudgetFilter, _>::from_snapshot(
+                &bytes,
+            )?;
+
+        assert_eq!(
+            restored.capacities().capacity(&fid)?,
+            storage.capacities().capacity(&fid)?,
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_nearest_exhaustion() -> Result<(), anyhow::Error> {
+        let capacities = StaticCapacities::mock();
+        let mut storage: HashMapFilterStorage<PureDPBudgetFilter, _> =
+            HashMapFilterStorage::new(capacities)?;
+
+        let low: FilterId<i32, ()> = FilterId::C(1);
+        let high: FilterId<i32, ()> = FilterId::C(2);
+        storage.try_consume(&low, &9.0)?;
+        storage.try_consume(&high, &1.0)?;
+
+        let nearest = storage.nearest_exhaustion(1)?;
+        assert_eq!(nearest.len(), 1);
+        assert_eq!(nearest[0].0, low);
+        assert_eq!(nearest[0].1.remaining, 11.0);
+        assert_eq!(nearest[0].1.capacity, 20.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_maintain_drops_filters_past_the_retention_window(
+    ) -> Result<(), anyhow::Error> {
+        let capacities: StaticCapacities<FilterId<i32, ()>, f64> =
+            StaticCapacities::mock().with_retention(RetentionPolicy {
+                window_epochs: 2,
+                max_live_filters: 1,
+            });
+        let mut storage: HashMapFilterStorage<PureDPBudgetFilter, _> =
+            HashMapFilterStorage::new(capacities)?;
+
+        let stale = FilterId::C(1);
+        let live = FilterId::C(9);
+        storage.try_consume(&stale, &1.0)?;
+        storage.try_consume(&live, &1.0)?;
+
+        // now_epoch=9, window_epochs=2 -> anything older than epoch 7 is
+        // past the horizon.
+        let dropped = storage.maintain(9)?;
+        assert_eq!(dropped, 1);
+
+        // Stale filter is gone, so it comes back at full capacity.
+        assert_eq!(storage.remaining_budget(&stale)?, 20.0);
+        // Live filter, still inside the window, must be untouched even
+        // though max_live_filters (1) would otherwise be satisfied already.
+        assert_eq!(storage.remaining_budget(&live)?, 19.0);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,266 @@
+use std::{collections::HashMap, fmt::Debug};
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::budget::{
+    pure_dp_filter::{PureDPBudget, PureDPBudgetFilter},
+    sqlite_filter_storage::SqliteFilterStorageError,
+    traits::{EpochScopedFilterId, FilterCapacities, FilterStorage},
+};
+
+/// `FilterStorage` that keeps recent-epoch filters in an in-memory "hot" map,
+/// backed by write-through persistence to a SQLite-backed "cold" store, so
+/// consumed budget survives a process restart the moment it's spent, not
+/// only once a filter ages out of the hot window.
+///
+/// Unlike [`SqliteFilterStorage`](crate::budget::sqlite_filter_storage::SqliteFilterStorage),
+/// which treats SQLite as the single source of truth and a cache in front of
+/// it, here `hot` stays authoritative for reads while the process is alive
+/// (so a live session never pays a SQLite round trip just to read a filter
+/// it already faulted in); [`Self::age_out_to_cold`] only bounds the memory
+/// `hot` holds by evicting entries that are already durably persisted, it is
+/// not the sole path to durability.
+pub struct HotColdFilterStorage<C>
+where
+    C: FilterCapacities<Budget = PureDPBudget>,
+{
+    capacities: C,
+    hot: HashMap<C::FilterId, PureDPBudgetFilter>,
+    cold: Connection,
+}
+
+impl<C> HotColdFilterStorage<C>
+where
+    C: FilterCapacities<Budget = PureDPBudget>,
+    C::Error: Into<anyhow::Error>,
+    C::FilterId: std::fmt::Display,
+{
+    /// Opens (creating if needed) the cold SQLite store backing this
+    /// storage, with an empty hot layer.
+    pub fn open(
+        cold: Connection,
+        capacities: C,
+    ) -> Result<Self, SqliteFilterStorageError> {
+        cold.execute(
+            "CREATE TABLE IF NOT EXISTS cold_filters (
+                filter_id TEXT PRIMARY KEY,
+                consumed REAL NOT NULL
+            )",
+            [],
+        )?;
+        Ok(Self {
+            capacities,
+            hot: HashMap::new(),
+            cold,
+        })
+    }
+
+    fn load_from_cold(
+        &self,
+        filter_id: &C::FilterId,
+    ) -> Result<Option<PureDPBudgetFilter>, SqliteFilterStorageError> {
+        let consumed: Option<PureDPBudget> = self
+            .cold
+            .query_row(
+                "SELECT consumed FROM cold_filters WHERE filter_id = ?1",
+                params![filter_id.to_string()],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        Ok(match consumed {
+            Some(consumed) => Some(PureDPBudgetFilter {
+                consumed,
+                capacity: Some(
+                    self.capacities.capacity(filter_id).map_err(Into::into)?,
+                ),
+            }),
+            None => None,
+        })
+    }
+
+    fn flush_to_cold(
+        &self,
+        filter_id: &C::FilterId,
+        filter: &PureDPBudgetFilter,
+    ) -> Result<(), SqliteFilterStorageError> {
+        self.cold.execute(
+            "INSERT INTO cold_filters (filter_id, consumed) VALUES (?1, ?2)
+             ON CONFLICT(filter_id) DO UPDATE SET consumed = excluded.consumed",
+            params![filter_id.to_string(), filter.consumed],
+        )?;
+        Ok(())
+    }
+
+    /// Evicts every hot filter scoped to an epoch older than
+    /// `now_epoch - window_epochs` (the retention window on `capacities`,
+    /// see [`FilterCapacities::retention`]) from the hot map, so `hot` only
+    /// ever holds the current working set. A no-op if no retention policy is
+    /// configured. Returns the number of filters aged out.
+    ///
+    /// No flush happens here: [`Self::set_filter`] already write-throughs
+    /// every change to the cold store as it happens, so by the time a
+    /// filter ages out its cold copy is already current. This only bounds
+    /// `hot`'s memory, not durability.
+    pub fn age_out_to_cold(
+        &mut self,
+        now_epoch: <C::FilterId as EpochScopedFilterId>::Epoch,
+    ) -> Result<usize, SqliteFilterStorageError>
+    where
+        C::FilterId: EpochScopedFilterId + Clone + Eq + std::hash::Hash,
+        <C::FilterId as EpochScopedFilterId>::Epoch: Into<i64>,
+    {
+        let Some(retention) = self.capacities.retention() else {
+            return Ok(0);
+        };
+        let now_epoch: i64 = now_epoch.into();
+        let oldest_hot_epoch = now_epoch - retention.window_epochs as i64;
+
+        let aging_out: Vec<C::FilterId> = self
+            .hot
+            .keys()
+            .filter(|filter_id| filter_id.epoch().into() < oldest_hot_epoch)
+            .cloned()
+            .collect();
+
+        for filter_id in &aging_out {
+            self.hot.remove(filter_id);
+        }
+
+        Ok(aging_out.len())
+    }
+}
+
+impl<C> FilterStorage for HotColdFilterStorage<C>
+where
+    C: FilterCapacities<Budget = PureDPBudget>,
+    C::Error: Into<anyhow::Error>,
+    C::FilterId: Clone + Eq + std::hash::Hash + Debug + std::fmt::Display,
+{
+    type FilterId = C::FilterId;
+    type Budget = PureDPBudget;
+    type Filter = PureDPBudgetFilter;
+    type Capacities = C;
+    type Error = SqliteFilterStorageError;
+
+    fn new(capacities: Self::Capacities) -> Result<Self, Self::Error> {
+        Self::open(Connection::open_in_memory()?, capacities)
+    }
+
+    fn capacities(&self) -> &Self::Capacities {
+        &self.capacities
+    }
+
+    fn get_filter(
+        &mut self,
+        filter_id: &Self::FilterId,
+    ) -> Result<Option<Self::Filter>, Self::Error> {
+        if let Some(filter) = self.hot.get(filter_id) {
+            return Ok(Some(filter.clone()));
+        }
+
+        // Fault the filter back in from cold storage, if it's there.
+        match self.load_from_cold(filter_id)? {
+            Some(filter) => {
+                self.hot.insert(filter_id.clone(), filter.clone());
+                Ok(Some(filter))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn set_filter(
+        &mut self,
+        filter_id: &Self::FilterId,
+        filter: Self::Filter,
+    ) -> Result<(), Self::Error> {
+        // Write-through: persist to cold immediately, so budget consumed
+        // against a filter still inside the hot window survives a crash or
+        // restart, not only a filter that's already aged out. `hot` is
+        // still updated so reads for the rest of this session stay fast.
+        self.flush_to_cold(filter_id, &filter)?;
+        self.hot.insert(filter_id.clone(), filter);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        budget::traits::{FilterStatus, RetentionPolicy},
+        pds::quotas::{FilterId, StaticCapacities},
+    };
+
+    #[test]
+    fn test_fault_in_from_cold_after_aging_out(
+    ) -> Result<(), SqliteFilterStorageError> {
+        let capacities: StaticCapacities<FilterId<i32, ()>, PureDPBudget> =
+            StaticCapacities::mock().with_retention(RetentionPolicy {
+                window_epochs: 2,
+                max_live_filters: usize::MAX,
+            });
+        let mut storage =
+            HotColdFilterStorage::open(Connection::open_in_memory()?, capacities)?;
+
+        let fid = FilterId::C(1);
+        assert_eq!(storage.try_consume(&fid, &5.0)?, FilterStatus::Continue);
+
+        // now_epoch=9, window_epochs=2 -> epoch 1 is long out of the hot
+        // window, so it should be evicted from hot (its cold copy was
+        // already written through by set_filter).
+        let aged_out = storage.age_out_to_cold(9)?;
+        assert_eq!(aged_out, 1);
+        assert!(!storage.hot.contains_key(&fid));
+
+        // get_filter must transparently fault it back in from cold.
+        let filter = storage.get_filter(&fid)?.unwrap();
+        assert_eq!(filter.consumed, 5.0);
+        assert!(storage.hot.contains_key(&fid));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_filter_write_through_persists_before_aging_out(
+    ) -> Result<(), SqliteFilterStorageError> {
+        let capacities: StaticCapacities<FilterId<i32, ()>, PureDPBudget> =
+            StaticCapacities::mock().with_retention(RetentionPolicy {
+                window_epochs: 5,
+                max_live_filters: usize::MAX,
+            });
+        let mut storage =
+            HotColdFilterStorage::open(Connection::open_in_memory()?, capacities)?;
+
+        let fid = FilterId::C(8);
+        assert_eq!(storage.try_consume(&fid, &4.0)?, FilterStatus::Continue);
+
+        // Still well inside the hot window -- never aged out -- but the
+        // cold store must already have the consumed budget, so a crash
+        // right now wouldn't under-count it on restart.
+        let cold_filter = storage.load_from_cold(&fid)?.unwrap();
+        assert_eq!(cold_filter.consumed, 4.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_recent_filters_stay_hot() -> Result<(), SqliteFilterStorageError> {
+        let capacities: StaticCapacities<FilterId<i32, ()>, PureDPBudget> =
+            StaticCapacities::mock().with_retention(RetentionPolicy {
+                window_epochs: 5,
+                max_live_filters: usize::MAX,
+            });
+        let mut storage =
+            HotColdFilterStorage::open(Connection::open_in_memory()?, capacities)?;
+
+        let fid = FilterId::C(8);
+        storage.try_consume(&fid, &1.0)?;
+
+        let aged_out = storage.age_out_to_cold(9)?;
+        assert_eq!(aged_out, 0);
+        assert!(storage.hot.contains_key(&fid));
+
+        Ok(())
+    }
+}
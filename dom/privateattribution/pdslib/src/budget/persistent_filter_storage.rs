@@ -0,0 +1,213 @@
+use std::{fmt::Debug, hash::Hash};
+
+use im::HashMap as PersistentMap;
+
+use crate::budget::traits::{
+    Filter, FilterCapacities, FilterStatus, FilterStorage,
+};
+
+/// `FilterStorage` backed by a persistent (immutable, structurally-shared)
+/// map instead of a plain `HashMap`, so all-or-nothing deduction across
+/// several filters is a property of the data structure rather than of every
+/// call site manually probing each filter and rolling back by
+/// re-depositing on failure.
+///
+/// `begin()` saves the current root as `snapshot`, an O(1) clone thanks to
+/// structural sharing. Every `try_consume` until the next `commit`/`abort`
+/// mutates `filters` in place, producing a new version that still shares
+/// most of its structure with `snapshot`. `abort()` simply replaces
+/// `filters` with `snapshot`, discarding every mutation made during the
+/// transaction without needing to know which filters were touched or how to
+/// undo them individually. `commit()` just drops `snapshot`.
+pub struct PersistentFilterStorage<F, C>
+where
+    C: FilterCapacities,
+    F: Filter<C::Budget>,
+{
+    capacities: C,
+    filters: PersistentMap<C::FilterId, F>,
+    snapshot: Option<PersistentMap<C::FilterId, F>>,
+}
+
+impl<F, C> PersistentFilterStorage<F, C>
+where
+    C: FilterCapacities,
+    F: Filter<C::Budget> + Clone,
+    C::FilterId: Clone + Eq + Hash,
+{
+    /// Begins a transaction by saving a cheap snapshot of the current root.
+    pub fn begin(&mut self) {
+        self.snapshot = Some(self.filters.clone());
+    }
+
+    /// Commits the current transaction: the mutations already applied to
+    /// `filters` become permanent, and the saved snapshot is discarded.
+    pub fn commit(&mut self) {
+        self.snapshot = None;
+    }
+
+    /// Aborts the current transaction: restores `filters` to the snapshot
+    /// saved by `begin`, discarding every mutation made since. A no-op if no
+    /// transaction is open.
+    pub fn abort(&mut self) {
+        if let Some(snapshot) = self.snapshot.take() {
+            self.filters = snapshot;
+        }
+    }
+
+    /// Returns `true` while a transaction is open, i.e. between a `begin()`
+    /// and the matching `commit()`/`abort()`.
+    pub fn in_transaction(&self) -> bool {
+        self.snapshot.is_some()
+    }
+}
+
+impl<F, C> PersistentFilterStorage<F, C>
+where
+    C: FilterCapacities,
+    C::Error: Into<anyhow::Error>,
+    F: Filter<C::Budget> + Clone,
+    F::Error: Into<anyhow::Error>,
+    C::FilterId: Clone + Eq + Hash + Debug,
+{
+    /// Attempts to consume every `(filter_id, budget)` pair in
+    /// `filters_to_consume` inside a single transaction: if any filter is
+    /// out of budget, every filter touched so far in this call is rolled
+    /// back via `abort()` and none of them consume; otherwise the whole
+    /// batch is committed. Returns the ids of any out-of-budget filters.
+    pub fn try_consume_all(
+        &mut self,
+        filters_to_consume: &std::collections::HashMap<C::FilterId, C::Budget>,
+    ) -> Result<Vec<C::FilterId>, anyhow::Error> {
+        self.begin();
+
+        let mut oob_filters = vec![];
+        for (filter_id, budget) in filters_to_consume {
+            let status = self.try_consume(filter_id, budget)?;
+            if status == FilterStatus::OutOfBudget {
+                oob_filters.push(filter_id.clone());
+            }
+        }
+
+        if oob_filters.is_empty() {
+            self.commit();
+        } else {
+            self.abort();
+        }
+
+        Ok(oob_filters)
+    }
+}
+
+impl<F, C> FilterStorage for PersistentFilterStorage<F, C>
+where
+    F: Filter<C::Budget> + Clone,
+    F::Error: Into<anyhow::Error>,
+    C: FilterCapacities,
+    C::Error: Into<anyhow::Error>,
+    C::FilterId: Clone + Eq + Hash + Debug,
+{
+    type FilterId = C::FilterId;
+    type Filter = F;
+    type Budget = C::Budget;
+    type Capacities = C;
+    type Error = anyhow::Error;
+
+    fn new(capacities: Self::Capacities) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        Ok(Self {
+            capacities,
+            filters: PersistentMap::new(),
+            snapshot: None,
+        })
+    }
+
+    fn capacities(&self) -> &Self::Capacities {
+        &self.capacities
+    }
+
+    fn get_filter(
+        &mut self,
+        filter_id: &Self::FilterId,
+    ) -> Result<Option<Self::Filter>, Self::Error> {
+        Ok(self.filters.get(filter_id).cloned())
+    }
+
+    fn set_filter(
+        &mut self,
+        filter_id: &Self::FilterId,
+        filter: Self::Filter,
+    ) -> Result<(), Self::Error> {
+        self.filters.insert(filter_id.clone(), filter);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{
+        budget::pure_dp_filter::PureDPBudgetFilter,
+        pds::quotas::{FilterId, StaticCapacities},
+    };
+
+    #[test]
+    fn test_commit_keeps_consumption() -> Result<(), anyhow::Error> {
+        let capacities: StaticCapacities<FilterId<i32, ()>, f64> =
+            StaticCapacities::mock();
+        let mut storage: PersistentFilterStorage<PureDPBudgetFilter, _> =
+            PersistentFilterStorage::new(capacities)?;
+
+        let mut batch = HashMap::new();
+        batch.insert(FilterId::<i32, ()>::C(1), 5.0);
+
+        let oob = storage.try_consume_all(&batch)?;
+        assert!(oob.is_empty());
+        assert!(!storage.in_transaction());
+
+        let filter = storage.get_filter(&FilterId::C(1))?.unwrap();
+        assert_eq!(filter.consumed, 5.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_abort_restores_snapshot_on_any_depletion() -> Result<(), anyhow::Error>
+    {
+        let capacities: StaticCapacities<FilterId<i32, ()>, f64> =
+            StaticCapacities::mock();
+        let mut storage: PersistentFilterStorage<PureDPBudgetFilter, _> =
+            PersistentFilterStorage::new(capacities)?;
+
+        // Consume some budget from a filter that the next batch will also
+        // touch, so we can confirm it's untouched by the aborted batch.
+        let mut warmup = HashMap::new();
+        warmup.insert(FilterId::<i32, ()>::C(1), 5.0);
+        storage.try_consume_all(&warmup)?;
+
+        // This batch would push C(1) over capacity (20.0), so the whole
+        // batch must abort, including the untouched QTrigger filter.
+        let mut batch = HashMap::new();
+        batch.insert(FilterId::C(1), 100.0);
+        batch.insert(FilterId::QTrigger(1, ()), 1.0);
+
+        let oob = storage.try_consume_all(&batch)?;
+        assert_eq!(oob, vec![FilterId::C(1)]);
+        assert!(!storage.in_transaction());
+
+        // C(1) must still reflect only the warmup consumption.
+        let c_filter = storage.get_filter(&FilterId::C(1))?.unwrap();
+        assert_eq!(c_filter.consumed, 5.0);
+
+        // QTrigger was never set before the aborted batch, so it must still
+        // be absent rather than holding a partial consumption.
+        assert!(storage
+            .get_filter(&FilterId::QTrigger(1, ()))?
+            .is_none());
+
+        Ok(())
+    }
+}
@@ -1,7 +1,7 @@
 use core::f64;
 
 use log::{debug, warn};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::budget::traits::{Budget, Filter, FilterStatus};
 
@@ -13,14 +13,17 @@ use crate::budget::traits::{Budget, Filter, FilterStatus};
 /// simple f64 for epsilon and ignore floating point arithmetic issues.
 ///
 /// TODO(https://github.com/columbia/pdslib/issues/14): use OpenDP accountant (even though it seems
-///     to also use f64) or move to a positive rational type or fixed point.
-///     We could also generalize to RDP/zCDP.
+///     to also use f64) or move to a positive rational type or fixed point
+///     (see [`FixedPointBudgetFilter`](crate::budget::fixed_point_filter::FixedPointBudgetFilter)
+///     for an exact alternative that callers can migrate to).
+///     We could also generalize to RDP/zCDP (see
+///     [`ZCdpBudgetFilter`](crate::budget::zcdp_filter::ZCdpBudgetFilter)).
 pub type PureDPBudget = f64;
 
 impl Budget for PureDPBudget {}
 
 /// A filter for pure differential privacy.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PureDPBudgetFilter {
     pub consumed: PureDPBudget,
     pub capacity: Option<PureDPBudget>, // None = infinite budget
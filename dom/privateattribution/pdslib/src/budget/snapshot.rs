@@ -0,0 +1,107 @@
+use std::fmt::Display;
+
+use serde::{de::DeserializeOwned, Serialize};
+use thiserror::Error;
+
+/// Current on-disk schema version for filter storage snapshots.
+///
+/// Bump this whenever the persisted shape changes in a way that isn't
+/// forward-compatible, so `from_cbor_snapshot` can reject (or, in the
+/// future, migrate) snapshots written by older builds instead of silently
+/// misreading them.
+pub const SNAPSHOT_SCHEMA_VERSION: u8 = 1;
+
+/// Errors that can arise while freezing or thawing filter budget state.
+#[derive(Error, Debug)]
+pub enum SnapshotError {
+    #[error("snapshot is empty, missing schema-version byte")]
+    Truncated,
+
+    #[error(
+        "unsupported snapshot schema version {found}, this build supports {expected}"
+    )]
+    UnsupportedVersion { found: u8, expected: u8 },
+
+    #[error("filter id {0:?} did not round-trip through Display after restore")]
+    CorruptFilterId(String),
+
+    #[error("failed to encode snapshot as CBOR: {0}")]
+    Encode(#[from] ciborium::ser::Error<std::io::Error>),
+
+    #[error("failed to decode snapshot as CBOR: {0}")]
+    Decode(#[from] ciborium::de::Error<std::io::Error>),
+}
+
+/// Serializes `value` to CBOR, prefixed with [`SNAPSHOT_SCHEMA_VERSION`].
+pub fn to_cbor_snapshot<T: Serialize>(
+    value: &T,
+) -> Result<Vec<u8>, SnapshotError> {
+    let mut bytes = vec![SNAPSHOT_SCHEMA_VERSION];
+    ciborium::ser::into_writer(value, &mut bytes)?;
+    Ok(bytes)
+}
+
+/// Restores a value previously produced by [`to_cbor_snapshot`].
+///
+/// Checks the leading schema-version byte before attempting to decode the
+/// CBOR body, so a snapshot written by an incompatible build is rejected
+/// with [`SnapshotError::UnsupportedVersion`] rather than decoded into
+/// garbage.
+pub fn from_cbor_snapshot<T: DeserializeOwned>(
+    bytes: &[u8],
+) -> Result<T, SnapshotError> {
+    let (version, body) =
+        bytes.split_first().ok_or(SnapshotError::Truncated)?;
+    if *version != SNAPSHOT_SCHEMA_VERSION {
+        return Err(SnapshotError::UnsupportedVersion {
+            found: *version,
+            expected: SNAPSHOT_SCHEMA_VERSION,
+        });
+    }
+    Ok(ciborium::de::from_reader(body)?)
+}
+
+/// Checks that `filter_id` renders through its `Display` impl as exactly
+/// `expected_display`, returning [`SnapshotError::CorruptFilterId`] with the
+/// rendered string otherwise. A panic inside `Display` is not caught.
+pub fn validate_round_trip<FID: Display>(
+    filter_id: &FID,
+    expected_display: &str,
+) -> Result<(), SnapshotError> {
+    let rendered = filter_id.to_string();
+    if rendered != expected_display {
+        return Err(SnapshotError::CorruptFilterId(rendered));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cbor_round_trip() -> Result<(), SnapshotError> {
+        let original: Vec<(u64, f64)> = vec![(1, 0.5), (2, 1.0)];
+        let bytes = to_cbor_snapshot(&original)?;
+        assert_eq!(bytes[0], SNAPSHOT_SCHEMA_VERSION);
+
+        let restored: Vec<(u64, f64)> = from_cbor_snapshot(&bytes)?;
+        assert_eq!(restored, original);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rejects_unknown_version() {
+        let mut bytes = to_cbor_snapshot(&42u64).unwrap();
+        bytes[0] = SNAPSHOT_SCHEMA_VERSION + 1;
+
+        let err = from_cbor_snapshot::<u64>(&bytes).unwrap_err();
+        assert!(matches!(err, SnapshotError::UnsupportedVersion { .. }));
+    }
+
+    #[test]
+    fn test_rejects_empty_snapshot() {
+        let err = from_cbor_snapshot::<u64>(&[]).unwrap_err();
+        assert!(matches!(err, SnapshotError::Truncated));
+    }
+}
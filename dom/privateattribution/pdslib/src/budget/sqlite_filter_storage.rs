@@ -0,0 +1,598 @@
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Display},
+    hash::Hash,
+};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use thiserror::Error;
+
+use crate::{
+    budget::{
+        pure_dp_filter::{PureDPBudget, PureDPBudgetFilter},
+        traits::{BudgetSummary, FilterCapacities, FilterStatus, FilterStorage},
+    },
+    pds::quotas::PdsFilterStatus,
+};
+
+/// Errors from the SQLite-backed filter storage.
+#[derive(Error, Debug)]
+pub enum SqliteFilterStorageError {
+    #[error("sqlite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+
+    #[error(transparent)]
+    Capacity(#[from] anyhow::Error),
+}
+
+/// One step of the schema evolution, run once when `PRAGMA user_version` is
+/// below this migration's index in [`MIGRATIONS`]. Each migration runs
+/// inside its own transaction, so a failure partway through a migration
+/// can't leave the schema half-upgraded.
+type Migration = fn(&rusqlite::Transaction) -> Result<(), rusqlite::Error>;
+
+/// Ordered schema migrations, applied in order starting from the database's
+/// current `user_version`. Appending a migration here (and bumping nothing
+/// else) is how the `filters` schema should evolve going forward, e.g. when
+/// `PureDPBudget` gains fields or per-epoch columns are added for the
+/// attribution modes in `ppa_histogram.rs` — existing `pdslib.sqlite`
+/// profiles upgrade in place instead of silently failing or needing
+/// `clear_db`.
+const MIGRATIONS: &[Migration] = &[migration_v1_create_filters_table];
+
+fn migration_v1_create_filters_table(
+    tx: &rusqlite::Transaction,
+) -> Result<(), rusqlite::Error> {
+    tx.execute(
+        "CREATE TABLE IF NOT EXISTS filters (
+            filter_id TEXT PRIMARY KEY,
+            consumed REAL NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Runs every migration in [`MIGRATIONS`] above the database's current
+/// `user_version`, each in its own transaction, bumping `user_version` to
+/// match once it commits.
+fn run_migrations(conn: &mut Connection) -> Result<(), rusqlite::Error> {
+    let current_version: u32 =
+        conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+    for (index, migration) in MIGRATIONS.iter().enumerate() {
+        let version = (index + 1) as u32;
+        if version <= current_version {
+            continue;
+        }
+
+        let tx = conn.transaction()?;
+        migration(&tx)?;
+        tx.pragma_update(None, "user_version", version)?;
+        tx.commit()?;
+    }
+
+    Ok(())
+}
+
+/// `FilterStorage` backed by a SQLite table, so consumed budget survives
+/// process restarts instead of living only in a `HashMap`.
+///
+/// Reads are served from an in-memory write-through `cache` whenever possible,
+/// so hot epochs don't hit SQLite on every `compute_report`. Writes that
+/// touch several filters at once (see [`Self::consume_batch`]) still go
+/// straight to SQLite inside a single transaction, so the atomicity of a
+/// multi-filter deduction never depends on the cache being warm or correct.
+pub struct SqliteFilterStorage<C>
+where
+    C: FilterCapacities<Budget = PureDPBudget>,
+{
+    conn: Connection,
+    capacities: C,
+    cache: HashMap<C::FilterId, PureDPBudgetFilter>,
+}
+
+impl<C> SqliteFilterStorage<C>
+where
+    C: FilterCapacities<Budget = PureDPBudget>,
+    C::Error: Into<anyhow::Error>,
+    C::FilterId: Display,
+{
+    /// Opens the storage, running any schema migrations from
+    /// [`MIGRATIONS`] that the database hasn't seen yet.
+    pub fn open(
+        mut conn: Connection,
+        capacities: C,
+    ) -> Result<Self, SqliteFilterStorageError> {
+        run_migrations(&mut conn)?;
+        Ok(Self {
+            conn,
+            capacities,
+            cache: HashMap::new(),
+        })
+    }
+
+    fn capacity_of(
+        &self,
+        filter_id: &C::FilterId,
+    ) -> Result<PureDPBudget, SqliteFilterStorageError> {
+        Ok(self.capacities.capacity(filter_id).map_err(Into::into)?)
+    }
+
+    fn load_filter(
+        &self,
+        filter_id: &C::FilterId,
+    ) -> Result<Option<PureDPBudgetFilter>, SqliteFilterStorageError> {
+        let consumed: Option<PureDPBudget> = self
+            .conn
+            .query_row(
+                "SELECT consumed FROM filters WHERE filter_id = ?1",
+                params![filter_id.to_string()],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        Ok(match consumed {
+            Some(consumed) => Some(PureDPBudgetFilter {
+                consumed,
+                capacity: Some(self.capacity_of(filter_id)?),
+            }),
+            None => None,
+        })
+    }
+
+    /// Attempts to deduct `loss` from every filter in `filters_to_consume`
+    /// as a single atomic transaction, replacing the fragile dry-run/commit
+    /// dance with real atomicity: each filter's `UPDATE` is conditioned on
+    /// `consumed + loss <= capacity`, so a filter without enough budget
+    /// simply matches zero rows instead of being checked up front. If every
+    /// update affected a row, the transaction is committed and the cache is
+    /// refreshed to match. If any filter was out of budget, the whole
+    /// transaction is rolled back and no filter is touched.
+    pub fn consume_batch(
+        &mut self,
+        filters_to_consume: &HashMap<C::FilterId, PureDPBudget>,
+    ) -> Result<PdsFilterStatus<C::FilterId>, SqliteFilterStorageError>
+    where
+        C::FilterId: Clone + Eq + Hash + Debug,
+    {
+        let tx = self.conn.transaction()?;
+        let mut oob_filters = vec![];
+
+        for (filter_id, loss) in filters_to_consume {
+            let capacity = self.capacities.capacity(filter_id).map_err(Into::into)?;
+            let key = filter_id.to_string();
+
+            tx.execute(
+                "INSERT INTO filters (filter_id, consumed) VALUES (?1, 0.0)
+                 ON CONFLICT(filter_id) DO NOTHING",
+                params![key],
+            )?;
+
+            let rows_affected = tx.execute(
+                "UPDATE filters
+                 SET consumed = consumed + ?2
+                 WHERE filter_id = ?1 AND consumed + ?2 <= ?3",
+                params![key, loss, capacity],
+            )?;
+
+            if rows_affected == 0 {
+                oob_filters.push(filter_id.clone());
+            }
+        }
+
+        if !oob_filters.is_empty() {
+            tx.rollback()?;
+            return Ok(PdsFilterStatus::OutOfBudget(oob_filters));
+        }
+        tx.commit()?;
+
+        // Reload from SQLite rather than assuming the cache already held
+        // each filter's prior consumption: right after reopening a
+        // persisted DB, a filter can have budget consumed in SQLite from an
+        // earlier process but no cache entry yet, since nothing has faulted
+        // it in. `or_insert_with(.. consumed: 0.0 ..)` used to treat that
+        // absence as "never consumed" and add only this batch's `loss`,
+        // undercounting consumed budget in the cache (and in turn letting
+        // `set_filter` overwrite the DB's correct higher value with that
+        // too-low cached one). SQLite is the source of truth here (see the
+        // struct doc comment), so read back what it actually committed.
+        for filter_id in filters_to_consume.keys() {
+            let filter = self.load_filter(filter_id)?.ok_or_else(|| {
+                SqliteFilterStorageError::Capacity(anyhow::anyhow!(
+                    "filter {filter_id:?} committed in consume_batch but missing from SQLite"
+                ))
+            })?;
+            self.cache.insert(filter_id.clone(), filter);
+        }
+
+        Ok(PdsFilterStatus::Continue)
+    }
+}
+
+impl<C> FilterStorage for SqliteFilterStorage<C>
+where
+    C: FilterCapacities<Budget = PureDPBudget>,
+    C::Error: Into<anyhow::Error>,
+    C::FilterId: Clone + Eq + Hash + Debug + Display,
+{
+    type FilterId = C::FilterId;
+    type Budget = PureDPBudget;
+    type Filter = PureDPBudgetFilter;
+    type Capacities = C;
+    type Error = SqliteFilterStorageError;
+
+    fn new(capacities: Self::Capacities) -> Result<Self, Self::Error> {
+        Self::open(Connection::open_in_memory()?, capacities)
+    }
+
+    fn capacities(&self) -> &Self::Capacities {
+        &self.capacities
+    }
+
+    fn get_filter(
+        &mut self,
+        filter_id: &Self::FilterId,
+    ) -> Result<Option<Self::Filter>, Self::Error> {
+        if let Some(filter) = self.cache.get(filter_id) {
+            return Ok(Some(filter.clone()));
+        }
+
+        let filter = self.load_filter(filter_id)?;
+        if let Some(filter) = &filter {
+            self.cache.insert(filter_id.clone(), filter.clone());
+        }
+        Ok(filter)
+    }
+
+    fn set_filter(
+        &mut self,
+        filter_id: &Self::FilterId,
+        filter: Self::Filter,
+    ) -> Result<(), Self::Error> {
+        self.conn.execute(
+            "INSERT INTO filters (filter_id, consumed) VALUES (?1, ?2)
+             ON CONFLICT(filter_id) DO UPDATE SET consumed = excluded.consumed",
+            params![filter_id.to_string(), filter.consumed],
+        )?;
+        self.cache.insert(filter_id.clone(), filter);
+        Ok(())
+    }
+
+    /// Runs a single `SELECT ... WHERE filter_id IN (...)` for every id not
+    /// already cached, instead of one round trip per filter.
+    fn get_filters(
+        &mut self,
+        filter_ids: &[Self::FilterId],
+    ) -> Result<HashMap<Self::FilterId, Self::Filter>, Self::Error> {
+        let mut filters = HashMap::new();
+        let mut missing = Vec::new();
+
+        for filter_id in filter_ids {
+            if let Some(filter) = self.cache.get(filter_id) {
+                filters.insert(filter_id.clone(), filter.clone());
+            } else {
+                missing.push(filter_id.clone());
+            }
+        }
+
+        if missing.is_empty() {
+            return Ok(filters);
+        }
+
+        let key_to_id: HashMap<String, &C::FilterId> =
+            missing.iter().map(|id| (id.to_string(), id)).collect();
+        let placeholders = missing.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+        let query = format!(
+            "SELECT filter_id, consumed FROM filters WHERE filter_id IN ({placeholders})"
+        );
+        let keys: Vec<String> =
+            missing.iter().map(|id| id.to_string()).collect();
+
+        let mut stmt = self.conn.prepare(&query)?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(keys.iter()), |row| {
+            let key: String = row.get(0)?;
+            let consumed: PureDPBudget = row.get(1)?;
+            Ok((key, consumed))
+        })?;
+
+        for row in rows {
+            let (key, consumed) = row?;
+            if let Some(&filter_id) = key_to_id.get(&key) {
+                let capacity = self.capacity_of(filter_id)?;
+                let filter = PureDPBudgetFilter {
+                    consumed,
+                    capacity: Some(capacity),
+                };
+                filters.insert(filter_id.clone(), filter.clone());
+            }
+        }
+
+        for (filter_id, filter) in &filters {
+            self.cache.insert(filter_id.clone(), filter.clone());
+        }
+
+        Ok(filters)
+    }
+
+    /// Runs one batched `INSERT ... ON CONFLICT DO UPDATE` inside a single
+    /// transaction, instead of one round trip per filter.
+    fn set_filters(
+        &mut self,
+        updates: Vec<(Self::FilterId, Self::Filter)>,
+    ) -> Result<(), Self::Error> {
+        if updates.is_empty() {
+            return Ok(());
+        }
+
+        let tx = self.conn.transaction()?;
+        for (filter_id, filter) in &updates {
+            tx.execute(
+                "INSERT INTO filters (filter_id, consumed) VALUES (?1, ?2)
+                 ON CONFLICT(filter_id) DO UPDATE SET consumed = excluded.consumed",
+                params![filter_id.to_string(), filter.consumed],
+            )?;
+        }
+        tx.commit()?;
+
+        for (filter_id, filter) in updates {
+            self.cache.insert(filter_id, filter);
+        }
+        Ok(())
+    }
+
+    /// Delegates to [`Self::consume_batch`], which already checks and debits
+    /// every filter inside a single SQLite transaction, so a late
+    /// out-of-budget filter rolls back every write rather than leaving
+    /// earlier filters debited.
+    fn try_consume_all(
+        &mut self,
+        requests: &[(Self::FilterId, Self::Budget)],
+    ) -> Result<FilterStatus, Self::Error> {
+        let filters_to_consume: HashMap<C::FilterId, PureDPBudget> =
+            requests.iter().cloned().collect();
+
+        match self.consume_batch(&filters_to_consume)? {
+            PdsFilterStatus::Continue => Ok(FilterStatus::Continue),
+            PdsFilterStatus::OutOfBudget(_) => Ok(FilterStatus::OutOfBudget),
+        }
+    }
+
+    /// Computes the filter count and total consumed budget with a single
+    /// `SELECT COUNT(*), SUM(consumed) FROM filters` aggregate, then reads
+    /// the per-filter breakdown with one more query over the whole table
+    /// rather than issuing a separate round trip per filter.
+    fn budget_summary(&mut self) -> Result<BudgetSummary, Self::Error> {
+        let (filter_count, total_consumed): (i64, Option<f64>) = self
+            .conn
+            .query_row(
+                "SELECT COUNT(*), SUM(consumed) FROM filters",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+
+        let mut stmt = self
+            .conn
+            .prepare("SELECT filter_id, consumed FROM filters")?;
+        let rows = stmt.query_map([], |row| {
+            let filter_id: String = row.get(0)?;
+            let consumed: f64 = row.get(1)?;
+            Ok((filter_id, consumed))
+        })?;
+
+        let mut consumed_by_filter_id = HashMap::new();
+        for row in rows {
+            let (filter_id, consumed) = row?;
+            consumed_by_filter_id.insert(filter_id, consumed);
+        }
+
+        Ok(BudgetSummary {
+            filter_count: filter_count as usize,
+            total_consumed: total_consumed.unwrap_or(0.0),
+            consumed_by_filter_id,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pds::quotas::{FilterId, StaticCapacities};
+
+    #[test]
+    fn test_consume_batch_commits_when_all_have_budget(
+    ) -> Result<(), SqliteFilterStorageError> {
+        let capacities: StaticCapacities<FilterId<u64, String>, PureDPBudget> =
+            StaticCapacities::mock();
+        let mut storage =
+            SqliteFilterStorage::open(Connection::open_in_memory()?, capacities)?;
+
+        let mut batch = HashMap::new();
+        batch.insert(FilterId::C(1), 5.0);
+        batch.insert(FilterId::QTrigger(1, "trigger.com".to_string()), 1.0);
+
+        assert_eq!(storage.consume_batch(&batch)?, PdsFilterStatus::Continue);
+
+        let filter = storage.get_filter(&FilterId::C(1))?.unwrap();
+        assert_eq!(filter.consumed, 5.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_consume_batch_rolls_back_on_any_out_of_budget(
+    ) -> Result<(), SqliteFilterStorageError> {
+        let capacities: StaticCapacities<FilterId<u64, String>, PureDPBudget> =
+            StaticCapacities::mock();
+        let mut storage =
+            SqliteFilterStorage::open(Connection::open_in_memory()?, capacities)?;
+
+        let mut first = HashMap::new();
+        first.insert(FilterId::C(1), 5.0);
+        storage.consume_batch(&first)?;
+
+        let mut second = HashMap::new();
+        second.insert(FilterId::C(1), 16.0); // 5 + 16 would exceed capacity of 20
+        second.insert(FilterId::QTrigger(1, "trigger.com".to_string()), 1.0);
+
+        let status = storage.consume_batch(&second)?;
+        assert!(matches!(status, PdsFilterStatus::OutOfBudget(_)));
+
+        // Unaffected filters must not have been touched by the rolled-back
+        // transaction.
+        let trigger_filter = storage
+            .get_filter(&FilterId::QTrigger(1, "trigger.com".to_string()))?;
+        assert!(trigger_filter.is_none());
+
+        let c_filter = storage.get_filter(&FilterId::C(1))?.unwrap();
+        assert_eq!(c_filter.consumed, 5.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_filters_then_get_filters_round_trips_in_one_batch(
+    ) -> Result<(), SqliteFilterStorageError> {
+        let capacities: StaticCapacities<FilterId<u64, String>, PureDPBudget> =
+            StaticCapacities::mock();
+        let mut storage =
+            SqliteFilterStorage::open(Connection::open_in_memory()?, capacities)?;
+
+        let c_filter = storage.get_filter_or_new(&FilterId::C(1))?;
+        let trigger_filter =
+            storage.get_filter_or_new(&FilterId::QTrigger(1, "trigger.com".to_string()))?;
+
+        storage.set_filters(vec![
+            (FilterId::C(1), c_filter),
+            (
+                FilterId::QTrigger(1, "trigger.com".to_string()),
+                trigger_filter,
+            ),
+        ])?;
+
+        // Force a read from SQLite rather than the cache, to exercise the
+        // batched `SELECT ... WHERE filter_id IN (...)`.
+        storage.cache.clear();
+
+        let fetched = storage.get_filters(&[
+            FilterId::C(1),
+            FilterId::QTrigger(1, "trigger.com".to_string()),
+            FilterId::C(2),
+        ])?;
+
+        assert_eq!(fetched.len(), 2);
+        assert!(fetched.contains_key(&FilterId::C(1)));
+        assert!(
+            fetched.contains_key(&FilterId::QTrigger(1, "trigger.com".to_string()))
+        );
+        assert!(!fetched.contains_key(&FilterId::C(2)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_try_consume_all_rolls_back_every_filter_on_one_out_of_budget(
+    ) -> Result<(), SqliteFilterStorageError> {
+        let capacities: StaticCapacities<FilterId<u64, String>, PureDPBudget> =
+            StaticCapacities::mock();
+        let mut storage =
+            SqliteFilterStorage::open(Connection::open_in_memory()?, capacities)?;
+
+        let requests = vec![
+            (FilterId::C(1), 5.0),
+            (FilterId::QTrigger(1, "trigger.com".to_string()), 100.0), // exceeds capacity
+        ];
+
+        let status = storage.try_consume_all(&requests)?;
+        assert_eq!(status, FilterStatus::OutOfBudget);
+
+        // Neither filter should have been touched by the rolled-back
+        // transaction, including the one that did have enough budget.
+        assert!(storage.get_filter(&FilterId::C(1))?.is_none());
+        assert!(storage
+            .get_filter(&FilterId::QTrigger(1, "trigger.com".to_string()))?
+            .is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_consume_batch_accounts_for_consumption_from_before_reopen(
+    ) -> Result<(), SqliteFilterStorageError> {
+        let capacities: StaticCapacities<FilterId<u64, String>, PureDPBudget> =
+            StaticCapacities::mock();
+        let mut storage =
+            SqliteFilterStorage::open(Connection::open_in_memory()?, capacities)?;
+
+        let mut first = HashMap::new();
+        first.insert(FilterId::C(1), 12.0);
+        storage.consume_batch(&first)?;
+
+        // Simulate a process restart: a fresh `SqliteFilterStorage` opened
+        // on the same (persisted) connection, with an empty cache that has
+        // never faulted `FilterId::C(1)` in.
+        let capacities: StaticCapacities<FilterId<u64, String>, PureDPBudget> =
+            StaticCapacities::mock();
+        let mut reopened =
+            SqliteFilterStorage::open(storage.conn, capacities)?;
+        assert!(reopened.cache.is_empty());
+
+        let mut second = HashMap::new();
+        second.insert(FilterId::C(1), 3.0);
+        assert_eq!(
+            reopened.consume_batch(&second)?,
+            PdsFilterStatus::Continue
+        );
+
+        // The cache must reflect the true total (12.0 persisted before the
+        // "restart" + 3.0 from this batch), not just this batch's 3.0.
+        let cached = reopened.cache.get(&FilterId::C(1)).unwrap();
+        assert_eq!(cached.consumed, 15.0);
+
+        let filter = reopened.get_filter(&FilterId::C(1))?.unwrap();
+        assert_eq!(filter.consumed, 15.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_bumps_user_version_and_is_idempotent_on_reopen(
+    ) -> Result<(), SqliteFilterStorageError> {
+        let conn = Connection::open_in_memory()?;
+        let capacities: StaticCapacities<FilterId<u64, String>, PureDPBudget> =
+            StaticCapacities::mock();
+        let storage = SqliteFilterStorage::open(conn, capacities)?;
+
+        let user_version: u32 = storage
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        assert_eq!(user_version, MIGRATIONS.len() as u32);
+
+        // Reopening an already-migrated connection must not fail or redo
+        // any migration.
+        let capacities: StaticCapacities<FilterId<u64, String>, PureDPBudget> =
+            StaticCapacities::mock();
+        let reopened = SqliteFilterStorage::open(storage.conn, capacities)?;
+        let user_version: u32 = reopened
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        assert_eq!(user_version, MIGRATIONS.len() as u32);
+        Ok(())
+    }
+
+    #[test]
+    fn test_budget_summary_aggregates_across_all_filters(
+    ) -> Result<(), SqliteFilterStorageError> {
+        let capacities: StaticCapacities<FilterId<u64, String>, PureDPBudget> =
+            StaticCapacities::mock();
+        let mut storage =
+            SqliteFilterStorage::open(Connection::open_in_memory()?, capacities)?;
+
+        storage.try_consume(&FilterId::C(1), &5.0)?;
+        storage.try_consume(&FilterId::QTrigger(1, "trigger.com".to_string()), &1.0)?;
+
+        let summary = storage.budget_summary()?;
+        assert_eq!(summary.filter_count, 2);
+        assert_eq!(summary.total_consumed, 6.0);
+        assert_eq!(
+            summary.consumed_by_filter_id.get(&FilterId::C(1).to_string()),
+            Some(&5.0)
+        );
+        Ok(())
+    }
+}
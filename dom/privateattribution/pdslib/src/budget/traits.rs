@@ -1,4 +1,4 @@
-use std::fmt::Debug;
+use std::{collections::HashMap, fmt::Debug};
 
 /// Trait for privacy budgets
 pub trait Budget: Clone + Debug {
@@ -39,25 +39,73 @@ pub enum FilterStatus {
 pub trait FilterCapacities {
     type FilterId;
     type Budget: Budget;
-    type Error;
+    type Error: std::error::Error + Send + Sync + 'static;
 
     fn capacity(
         &self,
         filter_id: &Self::FilterId,
     ) -> Result<Self::Budget, Self::Error>;
+
+    /// Optional memory-bounding policy for the `FilterStorage` holding
+    /// filters with these capacities. `None` by default, meaning filters are
+    /// kept forever. Overridden by capacity types that want storages to
+    /// prune old epochs, e.g. [`StaticCapacities`](crate::pds::quotas::StaticCapacities).
+    fn retention(&self) -> Option<&RetentionPolicy> {
+        None
+    }
+}
+
+/// Bounds how many filters a `FilterStorage` keeps resident.
+///
+/// `window_epochs` is the hard bound: any filter scoped to an epoch older
+/// than `now_epoch - window_epochs` is past the privacy budget horizon (it
+/// can never be spent against again) and is always safe to drop.
+/// `max_live_filters` is a soft target for filters still inside that window;
+/// since those filters remain spendable, a storage must never evict one of
+/// them just to honor this target, or the DP guarantee for that epoch would
+/// be broken by letting it reappear with full capacity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct RetentionPolicy {
+    pub window_epochs: u64,
+    pub max_live_filters: usize,
+}
+
+/// Filter-id types whose identity is scoped to a single epoch, so a storage
+/// can tell which filters fall outside a [`RetentionPolicy`]'s window.
+pub trait EpochScopedFilterId {
+    type Epoch: Copy;
+
+    fn epoch(&self) -> Self::Epoch;
+}
+
+/// Filter-id types whose identity includes a filter kind (e.g. `Nc`/`C`/
+/// `QTrigger`/`QSource` for [`FilterId`](crate::pds::quotas::FilterId)), so
+/// metrics can be broken down per kind without depending on `FilterId`'s
+/// concrete shape.
+pub trait FilterKind {
+    /// A short, stable label for this filter's kind, suitable as a metrics
+    /// label value (e.g. Prometheus' `kind="Nc"`).
+    fn kind(&self) -> &'static str;
 }
 
 /// Trait for an interface or object that maintains a collection of filters.
+///
+/// `Filter` and `Capacities` are allowed to fail with their own distinct
+/// error types; `Self::Error` only needs to be constructible from both, the
+/// same pattern used by `PrivateDataServiceCore`'s `ERR: From<FS::Error>`
+/// bound. This keeps e.g. a capacity lookup error (`UnknownFilter`,
+/// `CapacityUnavailable`) type-distinguishable from a filter arithmetic
+/// error, instead of collapsing both into one opaque error type.
 pub trait FilterStorage {
     type FilterId: Debug;
     type Budget: Budget;
-    type Filter: Filter<Self::Budget, Error = Self::Error>;
+    type Filter: Filter<Self::Budget>;
     type Capacities: FilterCapacities<
         FilterId = Self::FilterId,
         Budget = Self::Budget,
-        Error = Self::Error,
     >;
-    type Error;
+    type Error: From<<Self::Filter as Filter<Self::Budget>>::Error>
+        + From<<Self::Capacities as FilterCapacities>::Error>;
 
     /// Create a new filter storage with the given capacities for new filters.
     fn new(capacities: Self::Capacities) -> Result<Self, Self::Error>
@@ -83,6 +131,40 @@ pub trait FilterStorage {
         filter: Self::Filter,
     ) -> Result<(), Self::Error>;
 
+    /// Gets several filters at once. The default implementation loops over
+    /// `get_filter`, i.e. one round trip per id; storages backed by a real
+    /// database should override this to run a single batched query instead,
+    /// since a typical report touches many per-epoch, per-source filters.
+    /// Filters that don't exist yet are simply absent from the result.
+    fn get_filters(
+        &mut self,
+        filter_ids: &[Self::FilterId],
+    ) -> Result<HashMap<Self::FilterId, Self::Filter>, Self::Error>
+    where
+        Self::FilterId: Clone + Eq + std::hash::Hash,
+    {
+        let mut filters = HashMap::new();
+        for filter_id in filter_ids {
+            if let Some(filter) = self.get_filter(filter_id)? {
+                filters.insert(filter_id.clone(), filter);
+            }
+        }
+        Ok(filters)
+    }
+
+    /// Stores several filters at once. The default implementation loops over
+    /// `set_filter`, i.e. one round trip per id; storages backed by a real
+    /// database should override this to run a single batched write instead.
+    fn set_filters(
+        &mut self,
+        updates: Vec<(Self::FilterId, Self::Filter)>,
+    ) -> Result<(), Self::Error> {
+        for (filter_id, filter) in updates {
+            self.set_filter(&filter_id, filter)?;
+        }
+        Ok(())
+    }
+
     /// Get the filter with the given ID from the storage, or return a new one
     /// with default capacity if it does not exist.
     fn get_filter_or_new(
@@ -124,6 +206,103 @@ pub trait FilterStorage {
         Ok(status)
     }
 
+    /// Evaluates `can_consume` for every filter in `requests`, and only if
+    /// all of them return `Continue` does it apply every debit via
+    /// `try_consume`. Gives callers atomic, all-or-nothing semantics across
+    /// several filters, e.g. when a single report must debit multiple
+    /// per-epoch, per-source filters as a unit: a partial failure must never
+    /// silently burn budget from the filters that did have room.
+    ///
+    /// The default implementation runs the dry run and the writes as two
+    /// separate passes with nothing tying them together as a single
+    /// transaction, so it's only atomic with respect to the *decision*
+    /// (every filter had budget), not against the storage being mutated by
+    /// something else in between the two passes. Storages backed by a real
+    /// database should override this to wrap the writes in an actual
+    /// transaction, e.g. [`SqliteFilterStorage`](crate::budget::sqlite_filter_storage::SqliteFilterStorage).
+    fn try_consume_all(
+        &mut self,
+        requests: &[(Self::FilterId, Self::Budget)],
+    ) -> Result<FilterStatus, Self::Error> {
+        for (filter_id, budget) in requests {
+            if self.can_consume(filter_id, budget)? == FilterStatus::OutOfBudget {
+                return Ok(FilterStatus::OutOfBudget);
+            }
+        }
+
+        for (filter_id, budget) in requests {
+            self.try_consume(filter_id, budget)?;
+        }
+
+        Ok(FilterStatus::Continue)
+    }
+
+    /// Starts a transaction: a buffer of filter deductions that are only
+    /// ever visible to further calls against the same transaction, until
+    /// `commit` writes all of them to storage at once (or `rollback`
+    /// discards them, writing nothing). Replaces the old pattern of calling
+    /// `can_consume` for every filter, then `try_consume` for every filter
+    /// again and hoping the two passes agree.
+    fn begin_transaction(&self) -> FilterTransaction<Self::FilterId, Self::Filter> {
+        FilterTransaction::new()
+    }
+
+    /// Tries to consume `budget` from `filter_id`, buffering the result in
+    /// `txn` instead of writing it to storage. The first time a given
+    /// `filter_id` is touched within `txn`, it's seeded from this storage's
+    /// committed state (or a fresh filter, same as `try_consume`);
+    /// subsequent calls for the same id within `txn` see the buffered
+    /// state, so a transaction that debits the same filter twice behaves
+    /// like two sequential `try_consume` calls would, without touching
+    /// storage until `commit`.
+    ///
+    /// The default implementation works for any `FilterStorage`, so
+    /// backends get transactions for free; it only needs `Self::Filter:
+    /// Clone`, same caveat as `try_consume_all`'s dry-run/apply split: this
+    /// isn't isolated against concurrent mutation of `self` in between
+    /// transaction calls, just against a partial write within the
+    /// transaction itself.
+    fn try_consume_in(
+        &mut self,
+        txn: &mut FilterTransaction<Self::FilterId, Self::Filter>,
+        filter_id: &Self::FilterId,
+        budget: &Self::Budget,
+    ) -> Result<FilterStatus, Self::Error>
+    where
+        Self::FilterId: Clone + Eq + std::hash::Hash,
+        Self::Filter: Clone,
+    {
+        let mut filter = match txn.pending.get(filter_id) {
+            Some(filter) => filter.clone(),
+            None => self.get_filter_or_new(filter_id)?,
+        };
+
+        let status = filter.try_consume(budget)?;
+        if status == FilterStatus::Continue {
+            txn.pending.insert(filter_id.clone(), filter);
+        }
+        Ok(status)
+    }
+
+    /// Writes every filter touched by `txn` to storage. Only call this
+    /// after every `try_consume_in` call against `txn` returned `Continue`;
+    /// if any returned `OutOfBudget`, call `rollback` instead.
+    fn commit(
+        &mut self,
+        txn: FilterTransaction<Self::FilterId, Self::Filter>,
+    ) -> Result<(), Self::Error>
+    where
+        Self::FilterId: Clone + Eq + std::hash::Hash,
+    {
+        self.set_filters(txn.into_updates())
+    }
+
+    /// Discards a transaction without writing anything to storage. Since
+    /// `try_consume_in` only ever mutates `txn`'s own buffer, rolling back
+    /// is just dropping it; this method exists to make that intent explicit
+    /// at call sites, symmetric with `commit`.
+    fn rollback(&mut self, _txn: FilterTransaction<Self::FilterId, Self::Filter>) {}
+
     /// Gets the remaining budget for a filter.
     /// WARNING: this method is for testing and local visualization only.
     fn remaining_budget(
@@ -137,4 +316,155 @@ pub trait FilterStorage {
         };
         Ok(budget)
     }
+
+    /// Aggregate, read-only statistics across every filter this storage
+    /// holds, suitable for an admin/metrics dashboard: totals plus a
+    /// per-filter-id breakdown of consumed budget (which, since `FilterId`'s `Display` already encodes
+    /// epoch and URI, doubles as a per-epoch/per-URI view without the
+    /// storage needing to know `FilterId`'s internal shape).
+    ///
+    /// The default implementation can't enumerate the filters a generic
+    /// `FilterStorage` holds (the trait has no "list all ids" primitive), so
+    /// it returns an empty summary. Storages that already keep their own
+    /// full filter set (e.g. a backing `HashMap`, or a SQL table) should
+    /// override this to compute it in one pass instead.
+    fn budget_summary(&mut self) -> Result<BudgetSummary, Self::Error>
+    where
+        Self::Budget: Into<f64>,
+    {
+        Ok(BudgetSummary::default())
+    }
+
+    /// Drops filters scoped to an epoch older than `oldest_live_epoch`: past
+    /// the privacy budget horizon, so they can never be spent against again,
+    /// and thus always safe to forget. A filter recreated after being
+    /// pruned starts back at full capacity, which is sound precisely
+    /// because that epoch can no longer be spent against -- indistinguishable
+    /// to the privacy accounting from that epoch never having been created.
+    ///
+    /// The default implementation can't enumerate the filters a generic
+    /// `FilterStorage` holds (same limitation as `budget_summary`), so it
+    /// returns 0; storages that keep their own full filter set should
+    /// override this. Returns the number of filters dropped.
+    fn prune(&mut self, _oldest_live_epoch: i64) -> Result<usize, Self::Error>
+    where
+        Self::FilterId: EpochScopedFilterId,
+        <Self::FilterId as EpochScopedFilterId>::Epoch: TryInto<i64>,
+    {
+        Ok(0)
+    }
+
+    /// Enumerates every filter this storage currently holds, each with its
+    /// remaining budget, for bulk introspection -- e.g. a budget dashboard
+    /// that wants every filter's state in one round trip instead of one
+    /// `remaining_budget` call per id. Filter ids come back as text (in
+    /// whatever form the storage keys them by, which need not be the
+    /// `Display` form of `BudgetSummary::consumed_by_filter_id`'s keys)
+    /// rather than `Self::FilterId`, since a generic storage has no way to
+    /// parse a stored id back into its typed form.
+    ///
+    /// The default implementation can't enumerate the filters a generic
+    /// `FilterStorage` holds (same limitation as `budget_summary`), so it
+    /// returns an empty list; storages that already keep their own full
+    /// filter set (e.g. a backing `HashMap`, or a SQL table) should override
+    /// this to compute it in one pass instead.
+    fn all_budgets(&mut self) -> Result<Vec<FilterBudgetEntry>, Self::Error>
+    where
+        Self::Budget: Into<f64>,
+    {
+        Ok(Vec::new())
+    }
+
+    /// Capacity, remaining budget, and utilization ratio for a single
+    /// filter, suitable for an admin metrics endpoint. Requires a
+    /// `Self::Budget: Into<f64>` bound (rather than on the trait itself) so
+    /// implementors that never call this don't need a numeric budget type.
+    fn utilization(
+        &mut self,
+        filter_id: &Self::FilterId,
+    ) -> Result<FilterUtilization<Self::Budget>, Self::Error>
+    where
+        Self::Budget: Into<f64>,
+    {
+        let capacity = self.capacities().capacity(filter_id)?;
+        let remaining = self.remaining_budget(filter_id)?;
+
+        let capacity_f64: f64 = capacity.clone().into();
+        let remaining_f64: f64 = remaining.clone().into();
+        let utilization_ratio = if capacity_f64 > 0.0 {
+            (capacity_f64 - remaining_f64) / capacity_f64
+        } else {
+            0.0
+        };
+
+        Ok(FilterUtilization {
+            capacity,
+            remaining,
+            utilization_ratio,
+        })
+    }
+}
+
+/// Capacity, remaining budget, and utilization ratio for a single filter,
+/// as returned by [`FilterStorage::utilization`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterUtilization<B> {
+    pub capacity: B,
+    pub remaining: B,
+
+    /// Fraction of capacity consumed, in `[0.0, 1.0]`.
+    pub utilization_ratio: f64,
+}
+
+/// A buffered, not-yet-applied set of filter deductions, returned by
+/// `FilterStorage::begin_transaction` and consumed by `commit`/`rollback`.
+/// See `FilterStorage::try_consume_in`.
+#[derive(Debug)]
+pub struct FilterTransaction<Id, F> {
+    pending: HashMap<Id, F>,
+}
+
+impl<Id, F> FilterTransaction<Id, F> {
+    fn new() -> Self {
+        Self {
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Consumes the transaction, returning its buffered filter writes.
+    /// `FilterStorage::commit`'s default implementation uses this; storages
+    /// that override `commit` to flush in a single batch (e.g. to a durable
+    /// backend) should use this rather than looping over `set_filter`.
+    pub fn into_updates(self) -> Vec<(Id, F)> {
+        self.pending.into_iter().collect()
+    }
+}
+
+/// Aggregate, storage-wide budget statistics, as returned by
+/// [`FilterStorage::budget_summary`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BudgetSummary {
+    pub filter_count: usize,
+    pub total_consumed: f64,
+
+    /// Consumed budget per filter id (its `Display` string), doubling as a
+    /// per-epoch/per-URI breakdown since that's what `FilterId`'s `Display`
+    /// impls encode. Total capacity isn't included here: it's a function of
+    /// `FilterCapacities`, not data in the table, so aggregating it would
+    /// mean resolving every filter id back to its typed form, defeating the
+    /// point of a single aggregate query. Callers that need per-id capacity
+    /// should pair this with `FilterStorage::utilization` for the ids they
+    /// care about.
+    pub consumed_by_filter_id: HashMap<String, f64>,
+}
+
+/// A single filter's id (as text) and remaining budget, as returned by
+/// [`FilterStorage::all_budgets`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterBudgetEntry {
+    /// This filter's id, in the text form the implementing storage keys it
+    /// by; not necessarily the `Display` form used for
+    /// `BudgetSummary::consumed_by_filter_id`'s keys.
+    pub filter_id: String,
+    pub remaining: f64,
 }
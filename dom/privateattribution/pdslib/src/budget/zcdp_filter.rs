@@ -0,0 +1,147 @@
+use core::f64;
+
+use log::{debug, warn};
+use serde::Serialize;
+
+use crate::budget::traits::{Budget, Filter, FilterStatus};
+
+/// A simple floating-point budget for zero-concentrated differential privacy
+/// (zCDP), with support for infinite budget.
+///
+/// The accounted quantity is ρ (rho), not ε: under composition, ρ accumulates
+/// additively (total ρ = Σρᵢ), same as `PureDPBudget`'s ε does for pure DP.
+/// Infinite budget can be used for noiseless testing queries and to
+/// deactivate filters by setting their capacity to `None`. We use a simple
+/// f64 for rho and ignore floating point arithmetic issues, same caveat as
+/// `PureDPBudget`.
+pub type ZCdpBudget = f64;
+
+impl Budget for ZCdpBudget {}
+
+/// Converts a ρ-zCDP guarantee into an (ε, δ)-DP guarantee, using the
+/// standard conversion `epsilon = rho + 2*sqrt(rho*ln(1/delta))`.
+pub fn to_approx_dp(rho: f64, delta: f64) -> f64 {
+    rho + 2.0 * (rho * (1.0 / delta).ln()).sqrt()
+}
+
+/// The ρ spent by a Gaussian mechanism with L2 sensitivity `l2_sensitivity`
+/// and noise standard deviation `sigma`: `rho = l2_sensitivity^2 / (2 *
+/// sigma^2)`.
+pub fn gaussian_rho(l2_sensitivity: f64, sigma: f64) -> f64 {
+    l2_sensitivity.powi(2) / (2.0 * sigma.powi(2))
+}
+
+/// The ρ spent by a pure ε-DP mechanism, via the standard conversion `rho =
+/// epsilon^2 / 2`.
+pub fn pure_dp_to_rho(epsilon: f64) -> f64 {
+    epsilon.powi(2) / 2.0
+}
+
+/// A filter for zero-concentrated differential privacy.
+#[derive(Debug, Clone, Serialize)]
+pub struct ZCdpBudgetFilter {
+    pub consumed: ZCdpBudget,
+    pub capacity: Option<ZCdpBudget>, // None = infinite budget
+}
+
+impl Filter<ZCdpBudget> for ZCdpBudgetFilter {
+    type Error = anyhow::Error;
+
+    fn new(capacity: ZCdpBudget) -> Result<Self, Self::Error> {
+        let this = Self {
+            consumed: 0.0,
+            capacity: Some(capacity),
+        };
+        Ok(this)
+    }
+
+    fn can_consume(
+        &self,
+        budget: &ZCdpBudget,
+    ) -> Result<FilterStatus, Self::Error> {
+        match self.capacity {
+            None => Ok(FilterStatus::Continue),
+            Some(capacity) => {
+                let remaining = capacity - self.consumed;
+
+                let diff = (remaining - budget).abs();
+                if diff < 1e-9 && diff > 0.0 {
+                    warn!(
+                        "can_consume: difference between remaining rho ({remaining}) and requested rho ({budget}) is very small, diff = {diff}",
+                    );
+                }
+
+                let out_of_budget = self.consumed + budget > capacity;
+                let status = match out_of_budget {
+                    true => FilterStatus::OutOfBudget,
+                    false => FilterStatus::Continue,
+                };
+                Ok(status)
+            }
+        }
+    }
+
+    fn try_consume(
+        &mut self,
+        budget: &ZCdpBudget,
+    ) -> Result<FilterStatus, Self::Error> {
+        debug!("The rho consumed in this epoch is {:?}, rho capacity for this epoch is {:?}, and we need to consume this much rho {:?}", self.consumed, self.capacity, budget);
+
+        let status = self.can_consume(budget)?;
+        if status == FilterStatus::Continue {
+            self.consumed += budget;
+        }
+        Ok(status)
+    }
+
+    fn remaining_budget(&self) -> Result<ZCdpBudget, anyhow::Error> {
+        match self.capacity {
+            None => Ok(f64::INFINITY),
+            Some(capacity) => Ok(capacity - self.consumed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zcdp_budget_filter() -> Result<(), anyhow::Error> {
+        let mut filter = ZCdpBudgetFilter::new(1.0)?;
+        assert_eq!(filter.try_consume(&0.5)?, FilterStatus::Continue);
+        assert_eq!(filter.try_consume(&0.6)?, FilterStatus::OutOfBudget);
+
+        // Test infinite capacity
+        let mut infinite_filter = ZCdpBudgetFilter {
+            consumed: 0.0,
+            capacity: None,
+        };
+        assert_eq!(
+            infinite_filter.try_consume(&100.0)?,
+            FilterStatus::Continue
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_gaussian_rho_matches_standard_conversion() {
+        // rho = delta^2 / (2 * sigma^2)
+        assert_eq!(gaussian_rho(2.0, 2.0), 0.5);
+    }
+
+    #[test]
+    fn test_pure_dp_to_rho_matches_standard_conversion() {
+        // rho = epsilon^2 / 2
+        assert_eq!(pure_dp_to_rho(1.0), 0.5);
+    }
+
+    #[test]
+    fn test_to_approx_dp_matches_standard_conversion() {
+        let rho = 0.5;
+        let delta = 1e-5;
+        let epsilon = to_approx_dp(rho, delta);
+        assert_eq!(epsilon, rho + 2.0 * (rho * (1.0 / delta).ln()).sqrt());
+    }
+}
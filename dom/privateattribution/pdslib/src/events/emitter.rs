@@ -0,0 +1,194 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::events::traits::{Event, EventUris};
+
+/// Opaque handle returned by [`EventEmitter::on`], used to later
+/// [`EventEmitter::unsubscribe`] the listener it names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+type UriPattern<U> = Box<dyn Fn(&EventUris<U>) -> bool + Send + Sync>;
+type Listener<E> = Box<dyn Fn(&E) -> anyhow::Result<()> + Send + Sync>;
+
+struct Subscription<E: Event> {
+    id: SubscriptionId,
+    uri_pattern: UriPattern<E::Uri>,
+    listener: Listener<E>,
+}
+
+/// Reactive fan-out for newly-saved events, keyed on `EventUris<U>` rather
+/// than any particular storage backend, so a caller can trigger attribution
+/// recomputation or telemetry hooks as events arrive instead of polling
+/// [`EventStorage`](crate::events::traits::EventStorage). Callers register a
+/// `uri_pattern` predicate with [`EventEmitter::on`] and [`EventEmitter`]
+/// owners call [`EventEmitter::dispatch`] after saving an event (e.g. right
+/// after `EventStorage::add_event` succeeds).
+pub struct EventEmitter<E: Event> {
+    subscriptions: Vec<Subscription<E>>,
+    next_id: AtomicU64,
+}
+
+impl<E: Event> Default for EventEmitter<E> {
+    fn default() -> Self {
+        Self {
+            subscriptions: Vec::new(),
+            next_id: AtomicU64::new(0),
+        }
+    }
+}
+
+impl<E: Event> EventEmitter<E> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `listener` to fire on every future [`EventEmitter::dispatch`]
+    /// whose event's [`EventUris`] matches `uri_pattern`. Both closures must
+    /// be `Send + Sync`, since [`EventEmitter::dispatch`] runs matching
+    /// listeners concurrently across a scoped thread pool.
+    pub fn on(
+        &mut self,
+        uri_pattern: impl Fn(&EventUris<E::Uri>) -> bool + Send + Sync + 'static,
+        listener: impl Fn(&E) -> anyhow::Result<()> + Send + Sync + 'static,
+    ) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        self.subscriptions.push(Subscription {
+            id,
+            uri_pattern: Box::new(uri_pattern),
+            listener: Box::new(listener),
+        });
+        id
+    }
+
+    /// Removes a previously registered listener. Returns `false` if `id` was
+    /// already unsubscribed (or never existed), mirroring the not-found-is-
+    /// not-fatal convention `FilterStorage`/`EventStorage` implementations
+    /// use elsewhere in this crate.
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        let before = self.subscriptions.len();
+        self.subscriptions.retain(|sub| sub.id != id);
+        self.subscriptions.len() != before
+    }
+
+    /// Invokes every listener whose `uri_pattern` matches `event`'s
+    /// [`EventUris`], running them concurrently across a scoped thread pool
+    /// since listeners are required to be `Send`. Every matching listener
+    /// runs to completion even if an earlier one errors (a slow or failing
+    /// telemetry hook shouldn't suppress attribution recomputation for
+    /// others); the first error encountered, in subscription order, is then
+    /// propagated. Returns the number of listeners notified.
+    pub fn dispatch(&self, event: &E) -> anyhow::Result<usize>
+    where
+        E: Sync,
+    {
+        let matching: Vec<&Subscription<E>> = self
+            .subscriptions
+            .iter()
+            .filter(|sub| (sub.uri_pattern)(event.event_uris()))
+            .collect();
+
+        let results: Vec<anyhow::Result<()>> = std::thread::scope(|scope| {
+            matching
+                .iter()
+                .map(|sub| scope.spawn(|| (sub.listener)(event)))
+                .collect::<Vec<_>>()
+                .into_iter()
+                .map(|handle| handle.join().expect("event listener panicked"))
+                .collect()
+        });
+
+        let notified = results.len();
+        for result in results {
+            result?;
+        }
+        Ok(notified)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use super::*;
+    use crate::events::simple_event::SimpleEvent;
+
+    fn mock_event(source_uri: &str) -> SimpleEvent {
+        let mut uris = EventUris::mock();
+        uris.source_uri = source_uri.to_string();
+        SimpleEvent {
+            id: 1,
+            epoch_number: 1,
+            event_key: Default::default(),
+            uris,
+        }
+    }
+
+    #[test]
+    fn test_dispatch_notifies_only_matching_listeners() -> anyhow::Result<()> {
+        let mut emitter = EventEmitter::new();
+        let matched = Arc::new(AtomicUsize::new(0));
+        let unmatched = Arc::new(AtomicUsize::new(0));
+
+        let matched_clone = matched.clone();
+        emitter.on(
+            |uris: &EventUris<String>| uris.source_uri == "a.example",
+            move |_event: &SimpleEvent| {
+                matched_clone.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            },
+        );
+
+        let unmatched_clone = unmatched.clone();
+        emitter.on(
+            |uris: &EventUris<String>| uris.source_uri == "b.example",
+            move |_event: &SimpleEvent| {
+                unmatched_clone.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            },
+        );
+
+        let notified = emitter.dispatch(&mock_event("a.example"))?;
+
+        assert_eq!(notified, 1);
+        assert_eq!(matched.load(Ordering::SeqCst), 1);
+        assert_eq!(unmatched.load(Ordering::SeqCst), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_unsubscribe_stops_future_dispatch() -> anyhow::Result<()> {
+        let mut emitter = EventEmitter::new();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let calls_clone = calls.clone();
+        let id = emitter.on(
+            |_uris: &EventUris<String>| true,
+            move |_event: &SimpleEvent| {
+                calls_clone.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            },
+        );
+
+        assert!(emitter.unsubscribe(id));
+        assert!(!emitter.unsubscribe(id));
+
+        emitter.dispatch(&mock_event("a.example"))?;
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_dispatch_propagates_listener_error() {
+        let mut emitter = EventEmitter::new();
+        emitter.on(
+            |_uris: &EventUris<String>| true,
+            |_event: &SimpleEvent| Err(anyhow::anyhow!("listener failed")),
+        );
+
+        let err = emitter.dispatch(&mock_event("a.example")).unwrap_err();
+        assert_eq!(err.to_string(), "listener failed");
+    }
+}
@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+
+/// Coarse priority tag bit-packed into an [`EventKey`], borrowed from the
+/// sat-rs event model. Budgeting/filtering logic can favor higher
+/// severities when deciding what to retain under memory or privacy
+/// pressure (see [`crate::events::traits::Event::severity`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum Severity {
+    Info = 0,
+    Low = 1,
+    Medium = 2,
+    High = 3,
+}
+
+impl Severity {
+    const fn from_bits(bits: u64) -> Self {
+        match bits {
+            0 => Severity::Info,
+            1 => Severity::Low,
+            2 => Severity::Medium,
+            _ => Severity::High,
+        }
+    }
+}
+
+const SEVERITY_SHIFT: u32 = 62;
+const GROUP_ID_SHIFT: u32 = 48;
+const GROUP_ID_BITS: u32 = 14;
+const GROUP_ID_MASK: u64 = (1 << GROUP_ID_BITS) - 1;
+const UNIQUE_ID_BITS: u32 = 48;
+const UNIQUE_ID_MASK: u64 = (1 << UNIQUE_ID_BITS) - 1;
+
+/// A `u64` event identifier with a [`Severity`] and group ID packed into its
+/// top 16 bits: top 2 bits severity, next 14 bits group ID, low 48 bits a
+/// unique event identifier. Replaces the previously opaque `event_key: u64`
+/// so storage/quota logic can read priority metadata straight off the key
+/// without a separate lookup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventKey(u64);
+
+impl EventKey {
+    /// Packs `severity`, `group_id`, and `unique_id` into an [`EventKey`].
+    /// `const fn` so static catalogs of event kinds can be declared as
+    /// `const` items, e.g. `const CLICK: EventKey = EventKey::new(Severity::Medium, 1, 0);`.
+    ///
+    /// `group_id` is truncated to its low 14 bits and `unique_id` to its low
+    /// 48 bits if they overflow those widths.
+    pub const fn new(severity: Severity, group_id: u16, unique_id: u64) -> Self {
+        let severity_bits = (severity as u64) << SEVERITY_SHIFT;
+        let group_bits = ((group_id as u64) & GROUP_ID_MASK) << GROUP_ID_SHIFT;
+        let unique_bits = unique_id & UNIQUE_ID_MASK;
+        Self(severity_bits | group_bits | unique_bits)
+    }
+
+    pub const fn severity(&self) -> Severity {
+        Severity::from_bits(self.0 >> SEVERITY_SHIFT)
+    }
+
+    pub const fn group_id(&self) -> u16 {
+        ((self.0 >> GROUP_ID_SHIFT) & GROUP_ID_MASK) as u16
+    }
+
+    pub const fn unique_id(&self) -> u64 {
+        self.0 & UNIQUE_ID_MASK
+    }
+
+    /// The raw packed representation, e.g. for use as a `HashMap`/bucket
+    /// key where only `u64`'s `Hash`/`Ord` impls are needed.
+    pub const fn as_u64(&self) -> u64 {
+        self.0
+    }
+}
+
+impl Default for EventKey {
+    /// `Severity::Info`, group `0`, unique id `0` -- the all-zero key, for
+    /// test fixtures and callers that don't care about priority.
+    fn default() -> Self {
+        EventKey::new(Severity::Info, 0, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trips_severity_group_and_unique_id() {
+        let key = EventKey::new(Severity::High, 12345, 1 << 47);
+        assert_eq!(key.severity(), Severity::High);
+        assert_eq!(key.group_id(), 12345);
+        assert_eq!(key.unique_id(), 1 << 47);
+    }
+
+    #[test]
+    fn test_truncates_overflowing_group_and_unique_id() {
+        let key = EventKey::new(Severity::Low, u16::MAX, u64::MAX);
+        assert_eq!(key.group_id(), (u16::MAX as u64 & GROUP_ID_MASK) as u16);
+        assert_eq!(key.unique_id(), u64::MAX & UNIQUE_ID_MASK);
+    }
+
+    #[test]
+    fn test_default_is_all_zero() {
+        let key = EventKey::default();
+        assert_eq!(key.severity(), Severity::Info);
+        assert_eq!(key.group_id(), 0);
+        assert_eq!(key.unique_id(), 0);
+    }
+}
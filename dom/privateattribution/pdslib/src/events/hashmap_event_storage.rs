@@ -1,6 +1,11 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, hash::Hash};
 
-use crate::events::traits::{Event, EventStorage};
+use serde::{de::DeserializeOwned, ser::SerializeStruct, Deserialize, Serialize};
+
+use crate::{
+    budget::snapshot::{self, SnapshotError},
+    events::traits::{Event, EventStorage, PageCursor},
+};
 
 /// A simple in-memory event storage. Stores a mapping of epoch id to epoch
 /// events, where each epoch events is just a vec of events.
@@ -20,6 +25,53 @@ impl<E: Event> HashMapEventStorage<E> {
     }
 }
 
+impl<E> Serialize for HashMapEventStorage<E>
+where
+    E: Event + Serialize,
+    E::EpochId: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("HashMapEventStorage", 1)?;
+        state.serialize_field("epochs", &self.epochs)?;
+        state.end()
+    }
+}
+
+/// On-disk shape of a [`HashMapEventStorage`] snapshot. Kept separate from
+/// the struct itself, same rationale as
+/// [`HashMapFilterStorageSnapshot`](crate::budget::hashmap_filter_storage::HashMapFilterStorage):
+/// the in-memory type shouldn't need a `Deserialize` bound on its `HashMap`
+/// key just to be constructed fresh via `new`.
+#[derive(Deserialize)]
+struct HashMapEventStorageSnapshot<EID: Eq + Hash, E> {
+    epochs: HashMap<EID, Vec<E>>,
+}
+
+impl<E> HashMapEventStorage<E>
+where
+    E: Event + Serialize + DeserializeOwned,
+    E::EpochId: Serialize + DeserializeOwned,
+{
+    /// Freezes the full `EpochId -> events` map into a versioned CBOR
+    /// snapshot, suitable for persisting across browser restarts.
+    pub fn to_snapshot(&self) -> Result<Vec<u8>, SnapshotError> {
+        snapshot::to_cbor_snapshot(self)
+    }
+
+    /// Thaws a snapshot produced by [`Self::to_snapshot`], checking the
+    /// schema-version byte before decoding the CBOR body.
+    pub fn from_snapshot(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        let snapshot: HashMapEventStorageSnapshot<E::EpochId, E> =
+            snapshot::from_cbor_snapshot(bytes)?;
+        Ok(Self {
+            epochs: snapshot.epochs,
+        })
+    }
+}
+
 impl<E> EventStorage for HashMapEventStorage<E>
 where
     E: Event + Clone,
@@ -43,4 +95,105 @@ where
         let iterator = events.into_iter();
         Ok(iterator)
     }
+
+    fn events_for_epoch_paged(
+        &mut self,
+        epoch_id: &<Self::Event as Event>::EpochId,
+        page_cursor: PageCursor,
+        page_size: usize,
+    ) -> Result<(Vec<Self::Event>, PageCursor), Self::Error> {
+        // Unlike the default impl, only the page itself gets cloned, not
+        // the whole epoch.
+        let page: Vec<E> = self
+            .epochs
+            .get(epoch_id)
+            .map(|events| {
+                events.iter().skip(page_cursor).take(page_size).cloned().collect()
+            })
+            .unwrap_or_default();
+
+        let next_cursor = page_cursor + page.len();
+        Ok((page, next_cursor))
+    }
+
+    fn prune_before(
+        &mut self,
+        is_stale: impl Fn(&<Self::Event as Event>::EpochId) -> bool,
+    ) -> Result<usize, Self::Error> {
+        let stale: Vec<E::EpochId> = self
+            .epochs
+            .keys()
+            .filter(|epoch_id| is_stale(epoch_id))
+            .cloned()
+            .collect();
+
+        for epoch_id in &stale {
+            self.epochs.remove(epoch_id);
+        }
+
+        Ok(stale.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Snapshotting is exercised here against a minimal local event type,
+    /// independent of `SimpleEvent`/`PpaEvent`.
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct MockEvent {
+        id: u64,
+        epoch_number: u64,
+    }
+
+    impl Event for MockEvent {
+        type EpochId = u64;
+        type Uri = String;
+
+        fn epoch_id(&self) -> Self::EpochId {
+            self.epoch_number
+        }
+
+        fn event_uris(&self) -> &crate::events::traits::EventUris<String> {
+            unimplemented!("not needed for snapshot round-trip tests")
+        }
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() -> Result<(), anyhow::Error> {
+        let mut storage = HashMapEventStorage::<MockEvent>::new();
+        storage.add_event(MockEvent { id: 1, epoch_number: 1 })?;
+        storage.add_event(MockEvent { id: 2, epoch_number: 1 })?;
+        storage.add_event(MockEvent { id: 3, epoch_number: 2 })?;
+
+        let bytes = storage.to_snapshot()?;
+        let mut restored = HashMapEventStorage::<MockEvent>::from_snapshot(&bytes)?;
+
+        let mut epoch_1_ids: Vec<u64> =
+            restored.events_for_epoch(&1)?.map(|event| event.id).collect();
+        epoch_1_ids.sort();
+        assert_eq!(epoch_1_ids, vec![1, 2]);
+
+        let epoch_2_ids: Vec<u64> =
+            restored.events_for_epoch(&2)?.map(|event| event.id).collect();
+        assert_eq!(epoch_2_ids, vec![3]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rejects_snapshot_from_unsupported_version() {
+        let mut storage = HashMapEventStorage::<MockEvent>::new();
+        storage
+            .add_event(MockEvent { id: 1, epoch_number: 1 })
+            .unwrap();
+
+        let mut bytes = storage.to_snapshot().unwrap();
+        bytes[0] = snapshot::SNAPSHOT_SCHEMA_VERSION + 1;
+
+        let err = HashMapEventStorage::<MockEvent>::from_snapshot(&bytes)
+            .unwrap_err();
+        assert!(matches!(err, SnapshotError::UnsupportedVersion { .. }));
+    }
 }
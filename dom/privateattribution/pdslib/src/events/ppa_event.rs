@@ -1,10 +1,12 @@
 use std::fmt::Debug;
 
+use serde::{Deserialize, Serialize};
+
 use super::traits::Uri;
 use crate::events::traits::{Event, EventUris};
 
 /// Impression event
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PpaEvent<U: Uri = String> {
     /// Event ID, e.g., counter or random ID. Unused in Firefox but kept for
     /// debugging purposes.
@@ -0,0 +1,85 @@
+use std::collections::HashSet;
+
+use crate::events::traits::Event;
+
+/// Checks that every id [`Event::parents`] references for `event` appears
+/// in `known_event_ids`, so provenance-aware attribution logic can walk a
+/// validated DAG instead of silently treating a dangling parent reference
+/// as an isolated event. Callers typically build `known_event_ids` from the
+/// same epoch set `event` was retrieved from (e.g. the ids of everything
+/// [`RelevantEvents::for_epoch`](crate::events::relevant_events::RelevantEvents::for_epoch)
+/// returns), since a provenance chain reaching outside the attribution
+/// window isn't resolvable anyway.
+pub fn verify_parents_exist<E: Event>(
+    event: &E,
+    known_event_ids: &HashSet<u64>,
+) -> bool {
+    event.parents().all(|parent_id| known_event_ids.contains(&parent_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{simple_event::SimpleEvent, traits::EventUris};
+
+    #[test]
+    fn test_default_parents_is_empty() {
+        let event = SimpleEvent {
+            id: 1,
+            epoch_number: 1,
+            event_key: Default::default(),
+            uris: EventUris::mock(),
+        };
+        assert_eq!(event.parents().count(), 0);
+        assert!(verify_parents_exist(&event, &HashSet::new()));
+    }
+
+    #[derive(Debug, Clone)]
+    struct EventWithParents {
+        id: u64,
+        epoch_number: u64,
+        uris: EventUris<String>,
+        parent_ids: Vec<u64>,
+    }
+
+    impl Event for EventWithParents {
+        type EpochId = u64;
+        type Uri = String;
+
+        fn epoch_id(&self) -> Self::EpochId {
+            self.epoch_number
+        }
+
+        fn event_uris(&self) -> &EventUris<String> {
+            &self.uris
+        }
+
+        fn parents(&self) -> Box<dyn Iterator<Item = u64> + '_> {
+            Box::new(self.parent_ids.iter().copied())
+        }
+    }
+
+    #[test]
+    fn test_verify_parents_exist_accepts_known_parents() {
+        let event = EventWithParents {
+            id: 2,
+            epoch_number: 1,
+            uris: EventUris::mock(),
+            parent_ids: vec![1],
+        };
+        let known_event_ids = HashSet::from([1]);
+        assert!(verify_parents_exist(&event, &known_event_ids));
+    }
+
+    #[test]
+    fn test_verify_parents_exist_rejects_dangling_parent() {
+        let event = EventWithParents {
+            id: 2,
+            epoch_number: 1,
+            uris: EventUris::mock(),
+            parent_ids: vec![1, 99],
+        };
+        let known_event_ids = HashSet::from([1]);
+        assert!(!verify_parents_exist(&event, &known_event_ids));
+    }
+}
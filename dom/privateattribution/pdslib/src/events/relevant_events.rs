@@ -1,6 +1,10 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    cell::Cell,
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    hash::{Hash, Hasher},
+};
 
-use super::traits::{Event, EventStorage, RelevantEventSelector};
+use super::traits::{Event, EventStorage, PageCursor, RelevantEventSelector};
 
 /// A struct that holds relevant events for a set of epochs.
 ///
@@ -9,6 +13,9 @@ use super::traits::{Event, EventStorage, RelevantEventSelector};
 #[derive(Debug, Clone)]
 pub struct RelevantEvents<E: Event> {
     pub events_per_epoch: HashMap<E::EpochId, Vec<E>>,
+
+    /// Cache for [`Self::fingerprint`], invalidated by [`Self::drop_epoch`].
+    fingerprint: Cell<Option<u64>>,
 }
 
 impl<E: Event> RelevantEvents<E> {
@@ -40,10 +47,86 @@ impl<E: Event> RelevantEvents<E> {
         Ok(this)
     }
 
+    /// Like `from_event_storage`, but fetches each epoch's events in
+    /// bounded-size pages via `EventStorage::events_for_epoch_paged` rather
+    /// than eagerly materializing the whole epoch, filtering each page
+    /// through the selector as it arrives so irrelevant events are
+    /// discarded before they accumulate. Mainly useful for large event
+    /// histories on storage backends that override the default paged
+    /// implementation with a real bounded-memory fetch.
+    pub fn from_event_storage_paged<ES>(
+        event_storage: &mut ES,
+        epoch_ids: &[E::EpochId],
+        selector: &impl RelevantEventSelector<Event = E>,
+        page_size: usize,
+    ) -> Result<Self, ES::Error>
+    where
+        ES: EventStorage<Event = E>,
+    {
+        let mut events_per_epoch = HashMap::new();
+
+        for epoch_id in epoch_ids {
+            let mut relevant_events = Vec::new();
+            let mut page_cursor: PageCursor = 0;
+
+            loop {
+                let (page, next_cursor) = event_storage
+                    .events_for_epoch_paged(epoch_id, page_cursor, page_size)?;
+                if page.is_empty() {
+                    break;
+                }
+
+                relevant_events.extend(
+                    page.into_iter()
+                        .filter(|event| selector.is_relevant_event(event)),
+                );
+                page_cursor = next_cursor;
+            }
+
+            events_per_epoch.insert(*epoch_id, relevant_events);
+        }
+
+        Ok(Self::from_mapping(events_per_epoch))
+    }
+
     /// Constructs a `RelevantEvents` instance directly from a mapping of
     /// epochs, to relevant events for each of those epochs.
     pub fn from_mapping(events_per_epoch: HashMap<E::EpochId, Vec<E>>) -> Self {
-        Self { events_per_epoch }
+        Self {
+            events_per_epoch,
+            fingerprint: Cell::new(None),
+        }
+    }
+
+    /// A stable (within this run), content-based fingerprint of every event
+    /// held across every epoch: two `RelevantEvents` with the same
+    /// fingerprint hold the same epochs and events (up to hash collisions),
+    /// even if constructed independently. Used by
+    /// [`PrivateDataServiceCore`](crate::pds::core::PrivateDataServiceCore)'s
+    /// query-compute cache to detect when it can reuse a previous
+    /// computation instead of recomputing it.
+    ///
+    /// Each epoch's events are folded into the total with a commutative
+    /// `wrapping_add`, so the result doesn't depend on `HashMap` iteration
+    /// order. Computed lazily and cached; [`Self::drop_epoch`] invalidates
+    /// the cache, since it changes the event set.
+    pub fn fingerprint(&self) -> u64 {
+        if let Some(cached) = self.fingerprint.get() {
+            return cached;
+        }
+
+        let mut total: u64 = 0;
+        for (epoch_id, events) in &self.events_per_epoch {
+            let mut hasher = DefaultHasher::new();
+            format!("{epoch_id:?}").hash(&mut hasher);
+            for event in events {
+                format!("{event:?}").hash(&mut hasher);
+            }
+            total = total.wrapping_add(hasher.finish());
+        }
+
+        self.fingerprint.set(Some(total));
+        total
     }
 
     /// Get the relevant events for a specific epoch.
@@ -69,5 +152,6 @@ impl<E: Event> RelevantEvents<E> {
     /// Drop and forget the given epoch and all its events.
     pub fn drop_epoch(&mut self, epoch_id: &E::EpochId) {
         self.events_per_epoch.remove(epoch_id);
+        self.fingerprint.set(None);
     }
 }
@@ -1,31 +1,30 @@
 use std::fmt::Debug;
 
+use pdslib_derive::Event;
+use serde::{Deserialize, Serialize};
+
 use super::traits::Uri;
-use crate::events::traits::{Event, EventUris};
+use crate::events::{event_key::EventKey, traits::EventUris};
 
 /// A barebones event type for testing and demo purposes. See ppa_event for a
 /// richer type.
-#[derive(Debug, Clone)]
+///
+/// `Event` is derived rather than hand-written (see `pdslib-derive`):
+/// `#[epoch_id]` and `#[event_uris]` tag which field backs each associated
+/// type, and `#[event_key]` additionally tags the packed key so the derive
+/// overrides `Event::severity()` to decode it, the same information the
+/// hand-written impl used to encode in its body.
+#[derive(Debug, Clone, Serialize, Deserialize, Event)]
 pub struct SimpleEvent<U: Uri = String> {
     pub id: u64,
+    #[epoch_id]
     pub epoch_number: u64,
-    pub event_key: u64,
+    #[event_key]
+    pub event_key: EventKey,
+    #[event_uris]
     pub uris: EventUris<U>,
 }
 
-impl<U: Uri> Event for SimpleEvent<U> {
-    type EpochId = u64;
-    type Uri = U;
-
-    fn epoch_id(&self) -> Self::EpochId {
-        self.epoch_number
-    }
-
-    fn event_uris(&self) -> &EventUris<U> {
-        &self.uris
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -35,7 +34,7 @@ mod tests {
         let event = SimpleEvent {
             id: 1,
             epoch_number: 1,
-            event_key: 3,
+            event_key: EventKey::new(crate::events::event_key::Severity::Info, 0, 3),
             uris: EventUris::mock(),
         };
         assert_eq!(event.id, 1);
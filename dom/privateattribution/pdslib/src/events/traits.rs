@@ -1,5 +1,9 @@
 use std::{fmt::Debug, hash::Hash};
 
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::events::event_key::Severity;
+
 /// Marker trait with bounds for epoch identifiers.
 pub trait EpochId: Clone + Copy + Debug + Eq + Hash {}
 
@@ -12,7 +16,7 @@ pub trait Uri: Hash + Eq + Clone + Debug {}
 /// Implement URI for all eligible types
 impl<T: Hash + Eq + Clone + Debug> Uri for T {}
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EventUris<U> {
     /// URI of the entity that registered this event.
     pub source_uri: U,
@@ -37,6 +41,59 @@ pub trait Event: Debug + Clone {
     fn epoch_id(&self) -> Self::EpochId;
 
     fn event_uris(&self) -> &EventUris<Self::Uri>;
+
+    /// Coarse priority used by quota logic to favor retaining high-severity
+    /// events under memory or privacy pressure (e.g.
+    /// [`EventStorage::prune_before`] callers picking what to drop first).
+    /// Defaults to [`Severity::Info`]; event types that bit-pack a
+    /// [`crate::events::event_key::EventKey`] (e.g. [`SimpleEvent`](crate::events::simple_event::SimpleEvent))
+    /// override this with their key's decoded severity.
+    fn severity(&self) -> Severity {
+        Severity::Info
+    }
+
+    /// Ids of the events this one was authorized by or derived from within
+    /// the same logical provenance chain, following the ruma
+    /// state-resolution `Event` design's `prev_events`/`auth_events` (e.g. a
+    /// conversion/trigger event naming the earlier impression events it
+    /// attributes to). Defaults to empty, so attribution logic that doesn't
+    /// care about provenance can ignore this entirely; event types that do
+    /// track lineage override it to return their recorded parent ids.
+    ///
+    /// Parent ids are plain `u64`s -- the same identifier
+    /// [`SimpleEvent`](crate::events::simple_event::SimpleEvent)/[`PpaEvent`](crate::events::ppa_event::PpaEvent)
+    /// already carry in their `id` field -- rather than a new associated
+    /// type, since `Event` has no event-identity type of its own and every
+    /// event type in this crate already has a `u64` id to spare.
+    /// See [`crate::events::provenance::verify_parents_exist`] for checking
+    /// that every referenced parent actually exists in a given epoch set.
+    fn parents(&self) -> Box<dyn Iterator<Item = u64> + '_> {
+        Box::new(std::iter::empty())
+    }
+
+    /// Encodes this event with the [`crate::events::wire`] postcard format,
+    /// so a Firefox-embedded store can checkpoint epoch buckets to disk and
+    /// reload them across browser restarts. Bounded on `Serialize` via a
+    /// method-level `where` rather than the trait itself, since not every
+    /// `Event` impl needs to be serializable.
+    #[cfg(feature = "postcard")]
+    fn to_bytes(&self) -> Result<Vec<u8>, crate::events::wire::EventWireError>
+    where
+        Self: Serialize,
+    {
+        crate::events::wire::to_postcard_bytes(self)
+    }
+
+    /// Decodes an event previously written by [`Event::to_bytes`].
+    #[cfg(feature = "postcard")]
+    fn from_bytes(
+        bytes: &[u8],
+    ) -> Result<Self, crate::events::wire::EventWireError>
+    where
+        Self: DeserializeOwned,
+    {
+        crate::events::wire::from_postcard_bytes(bytes)
+    }
 }
 
 /// Selector that can tag relevant events one by one or in bulk.
@@ -50,6 +107,13 @@ pub trait RelevantEventSelector {
     fn is_relevant_event(&self, event: &Self::Event) -> bool;
 }
 
+/// Opaque cursor for `EventStorage::events_for_epoch_paged`: how many
+/// events of the epoch's log have already been paged through. Storage
+/// backends that don't keep events in a vec (e.g. a future DB backend)
+/// can still honor this as "skip this many", even if they seek some other
+/// way internally.
+pub type PageCursor = usize;
+
 /// Interface to store events and retrieve them by epoch.
 pub trait EventStorage {
     type Event: Event;
@@ -63,4 +127,202 @@ pub trait EventStorage {
         &mut self,
         epoch_id: &<Self::Event as Event>::EpochId,
     ) -> Result<impl Iterator<Item = Self::Event>, Self::Error>;
+
+    /// Retrieves up to `page_size` events for `epoch_id`, starting after
+    /// `page_cursor` events have already been returned, along with the
+    /// cursor to pass in for the next page. An empty page means iteration
+    /// is exhausted.
+    ///
+    /// The default implementation re-paginates the eager `events_for_epoch`
+    /// fetch, so it doesn't save any memory on its own; backends that can
+    /// seek directly (e.g. a future DB backend keyed by event id) should
+    /// override this for an actual bounded-memory path.
+    fn events_for_epoch_paged(
+        &mut self,
+        epoch_id: &<Self::Event as Event>::EpochId,
+        page_cursor: PageCursor,
+        page_size: usize,
+    ) -> Result<(Vec<Self::Event>, PageCursor), Self::Error> {
+        let page: Vec<Self::Event> = self
+            .events_for_epoch(epoch_id)?
+            .skip(page_cursor)
+            .take(page_size)
+            .collect();
+        let next_cursor = page_cursor + page.len();
+        Ok((page, next_cursor))
+    }
+
+    /// Drops every epoch for which `is_stale` returns true, e.g. because it
+    /// fell outside a retention window. Pairs with
+    /// `FilterStorage::prune`: once an epoch's events are pruned, any later
+    /// `compute_report`/`account_for_passive_privacy_loss` referencing it
+    /// must see no relevant events, matching a never-created epoch exactly.
+    ///
+    /// Takes a predicate rather than a cutoff epoch id, since `EpochId` has
+    /// no ordering of its own; callers compare against whatever scheme
+    /// (an `Into<i64>` epoch number, a calendar window, ...) their storage
+    /// uses.
+    ///
+    /// The default implementation can't enumerate the epochs a generic
+    /// `EventStorage` holds, so it returns 0; storages that keep their own
+    /// full epoch set should override this. Returns the number of epochs
+    /// dropped.
+    fn prune_before(
+        &mut self,
+        _is_stale: impl Fn(&<Self::Event as Event>::EpochId) -> bool,
+    ) -> Result<usize, Self::Error> {
+        Ok(0)
+    }
+
+    /// Streams relevant events across the contiguous epoch window
+    /// `from_epoch..=to_epoch`, applying `selector` one event at a time as
+    /// the iterator is driven rather than materializing every epoch's
+    /// events up front the way `RelevantEvents::from_event_storage` does.
+    /// Epoch bounds are taken as `i64`, same convention as
+    /// `FilterStorage::prune`'s `oldest_live_epoch`, so the range doesn't
+    /// depend on whatever concrete `EpochId` type `Self::Event` uses.
+    ///
+    /// Each item is a `Result` rather than the whole call being wrapped in
+    /// one, since fetching epoch N+1 can fail even after epoch N already
+    /// streamed successfully.
+    ///
+    /// The default implementation still calls `events_for_epoch` once per
+    /// epoch in the range (so it doesn't save a storage round trip per
+    /// epoch), but only one epoch's events are ever alive at once, instead
+    /// of every epoch's events in the window; storages that can seek
+    /// directly over a key range (e.g. a future DB backend) should override
+    /// this for genuine bounded-memory streaming.
+    fn events_for_epoch_range<'a>(
+        &'a mut self,
+        from_epoch: i64,
+        to_epoch: i64,
+        selector: &'a impl RelevantEventSelector<Event = Self::Event>,
+    ) -> Box<dyn Iterator<Item = Result<Self::Event, Self::Error>> + 'a>
+    where
+        <Self::Event as Event>::EpochId: TryFrom<i64>,
+    {
+        let epoch_ids: Vec<<Self::Event as Event>::EpochId> = (from_epoch
+            ..=to_epoch)
+            .filter_map(|epoch_number| epoch_number.try_into().ok())
+            .collect();
+
+        Box::new(epoch_ids.into_iter().flat_map(move |epoch_id| {
+            match self.events_for_epoch(&epoch_id) {
+                Ok(events) => {
+                    let filtered: Vec<Result<Self::Event, Self::Error>> = events
+                        .filter(|event| selector.is_relevant_event(event))
+                        .map(Ok)
+                        .collect();
+                    filtered
+                }
+                Err(error) => vec![Err(error)],
+            }
+        }))
+    }
+}
+
+/// Builder for a contiguous epoch window passed to
+/// [`EventStorage::events_for_epoch_range`], so callers can write
+/// `EpochRangeQuery::new().from_epoch(1).to_epoch(10).matching(&mut storage,
+/// &selector)` instead of naming the method's positional arguments.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EpochRangeQuery {
+    from_epoch: i64,
+    to_epoch: i64,
+}
+
+impl EpochRangeQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_epoch(mut self, from_epoch: i64) -> Self {
+        self.from_epoch = from_epoch;
+        self
+    }
+
+    pub fn to_epoch(mut self, to_epoch: i64) -> Self {
+        self.to_epoch = to_epoch;
+        self
+    }
+
+    /// Runs the query against `storage`, streaming events matching
+    /// `selector` across `self`'s epoch window.
+    pub fn matching<'a, ES>(
+        self,
+        storage: &'a mut ES,
+        selector: &'a impl RelevantEventSelector<Event = ES::Event>,
+    ) -> Box<dyn Iterator<Item = Result<ES::Event, ES::Error>> + 'a>
+    where
+        ES: EventStorage,
+        <ES::Event as Event>::EpochId: TryFrom<i64>,
+    {
+        storage.events_for_epoch_range(self.from_epoch, self.to_epoch, selector)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{
+        event_key::{EventKey, Severity},
+        hashmap_event_storage::HashMapEventStorage,
+        simple_event::SimpleEvent,
+    };
+
+    struct EventKeySelector(u64);
+
+    impl RelevantEventSelector for EventKeySelector {
+        type Event = SimpleEvent;
+
+        fn is_relevant_event(&self, event: &Self::Event) -> bool {
+            event.event_key.unique_id() == self.0
+        }
+    }
+
+    fn mock_event(id: u64, epoch_number: u64, event_key: u64) -> SimpleEvent {
+        SimpleEvent {
+            id,
+            epoch_number,
+            event_key: EventKey::new(Severity::Info, 0, event_key),
+            uris: EventUris::mock(),
+        }
+    }
+
+    #[test]
+    fn test_events_for_epoch_range_streams_matching_events_across_epochs(
+    ) -> Result<(), anyhow::Error> {
+        let mut storage = HashMapEventStorage::new();
+        storage.add_event(mock_event(1, 1, 0))?; // matches
+        storage.add_event(mock_event(2, 1, 1))?; // filtered out
+        storage.add_event(mock_event(3, 2, 0))?; // matches
+        storage.add_event(mock_event(4, 5, 0))?; // outside range
+
+        let selector = EventKeySelector(0);
+        let events: Vec<SimpleEvent> = storage
+            .events_for_epoch_range(1, 3, &selector)
+            .collect::<Result<_, _>>()?;
+
+        let mut ids: Vec<u64> = events.iter().map(|e| e.id).collect();
+        ids.sort();
+        assert_eq!(ids, vec![1, 3]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_epoch_range_query_builder_matches_direct_call() -> Result<(), anyhow::Error> {
+        let mut storage = HashMapEventStorage::new();
+        storage.add_event(mock_event(1, 1, 0))?;
+
+        let selector = EventKeySelector(0);
+        let events: Vec<SimpleEvent> = EpochRangeQuery::new()
+            .from_epoch(1)
+            .to_epoch(1)
+            .matching(&mut storage, &selector)
+            .collect::<Result<_, _>>()?;
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].id, 1);
+        Ok(())
+    }
 }
@@ -0,0 +1,107 @@
+//! Postcard-based on-disk encoding for events, gated behind the `postcard`
+//! feature since most consumers of `pdslib` never need to persist events to
+//! disk themselves (e.g. an in-memory-only test harness). Declared as
+//! `#[cfg(feature = "postcard")] pub mod wire;` from `events`'s module root.
+
+#![cfg(feature = "postcard")]
+
+use serde::{de::DeserializeOwned, Serialize};
+use thiserror::Error;
+
+/// Current on-disk schema version for single-event wire encodings.
+///
+/// Bump this whenever the encoding changes in a way that isn't
+/// forward-compatible, so `from_postcard_bytes` can reject a buffer written
+/// by an older/newer build instead of silently misreading it. Distinct from
+/// [`crate::budget::snapshot::SNAPSHOT_SCHEMA_VERSION`]: events persist one
+/// at a time as they're registered, while budget/event-storage snapshots are
+/// versioned as a whole blob.
+pub const EVENT_WIRE_SCHEMA_VERSION: u8 = 1;
+
+/// Errors that can arise while encoding or decoding an event's on-disk
+/// representation.
+#[derive(Error, Debug)]
+pub enum EventWireError {
+    #[error("event buffer is empty, missing schema-version byte")]
+    Truncated,
+
+    #[error(
+        "unsupported event wire schema version {found}, this build supports {expected}"
+    )]
+    UnsupportedVersion { found: u8, expected: u8 },
+
+    #[error("failed to encode event: {0}")]
+    Encode(postcard::Error),
+
+    #[error("failed to decode event: {0}")]
+    Decode(postcard::Error),
+}
+
+/// Encodes `value` with postcard, prefixed with
+/// [`EVENT_WIRE_SCHEMA_VERSION`]. Postcard's compact, `no_std`-friendly
+/// encoding is a better fit than the CBOR used for budget/event-storage
+/// snapshots (see [`crate::budget::snapshot`]) for the much higher volume of
+/// individual events a Firefox-embedded store needs to persist per epoch.
+pub fn to_postcard_bytes<T: Serialize>(
+    value: &T,
+) -> Result<Vec<u8>, EventWireError> {
+    let mut bytes = vec![EVENT_WIRE_SCHEMA_VERSION];
+    postcard::to_extend(value, &mut bytes).map_err(EventWireError::Encode)?;
+    Ok(bytes)
+}
+
+/// Restores a value previously produced by [`to_postcard_bytes`].
+pub fn from_postcard_bytes<T: DeserializeOwned>(
+    bytes: &[u8],
+) -> Result<T, EventWireError> {
+    let (version, body) =
+        bytes.split_first().ok_or(EventWireError::Truncated)?;
+    if *version != EVENT_WIRE_SCHEMA_VERSION {
+        return Err(EventWireError::UnsupportedVersion {
+            found: *version,
+            expected: EVENT_WIRE_SCHEMA_VERSION,
+        });
+    }
+    postcard::from_bytes(body).map_err(EventWireError::Decode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{
+        event_key::EventKey, simple_event::SimpleEvent, traits::EventUris,
+    };
+
+    #[test]
+    fn test_postcard_round_trip() -> Result<(), EventWireError> {
+        let event = SimpleEvent {
+            id: 1,
+            epoch_number: 7,
+            event_key: EventKey::default(),
+            uris: EventUris::mock(),
+        };
+
+        let bytes = to_postcard_bytes(&event)?;
+        assert_eq!(bytes[0], EVENT_WIRE_SCHEMA_VERSION);
+
+        let restored: SimpleEvent = from_postcard_bytes(&bytes)?;
+        assert_eq!(restored.id, event.id);
+        assert_eq!(restored.epoch_number, event.epoch_number);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rejects_unsupported_version() {
+        let mut bytes = to_postcard_bytes(&42u64).unwrap();
+        bytes[0] = EVENT_WIRE_SCHEMA_VERSION + 1;
+
+        let err = from_postcard_bytes::<u64>(&bytes).unwrap_err();
+        assert!(matches!(err, EventWireError::UnsupportedVersion { .. }));
+    }
+
+    #[test]
+    fn test_rejects_empty_buffer() {
+        let err = from_postcard_bytes::<u64>(&[]).unwrap_err();
+        assert!(matches!(err, EventWireError::Truncated));
+    }
+}
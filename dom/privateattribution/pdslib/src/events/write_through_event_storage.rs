@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+
+use crate::{
+    events::traits::{Event, EventStorage, PageCursor},
+    util::persistence::{CacheUpdatePolicy, Readable, Writable},
+};
+
+/// [`EventStorage`] backed by an in-memory cache fronting a pluggable
+/// durable key/value store, keyed by epoch id, each value being that
+/// epoch's full event vec. Mirrors
+/// [`WriteThroughFilterStorage`](crate::budget::write_through_filter_storage::WriteThroughFilterStorage),
+/// since registering an event and consuming budget have the same
+/// durability requirement: a device restart shouldn't silently forget
+/// state that was already acted on.
+pub struct WriteThroughEventStorage<E, D>
+where
+    E: Event,
+{
+    cache: HashMap<E::EpochId, Vec<E>>,
+    durable: D,
+}
+
+impl<E, D> WriteThroughEventStorage<E, D>
+where
+    E: Event + Clone,
+    D: Readable<E::EpochId, Vec<E>> + Writable<E::EpochId, Vec<E>>,
+    <D as Readable<E::EpochId, Vec<E>>>::Error: Into<anyhow::Error>,
+    <D as Writable<E::EpochId, Vec<E>>>::Error: Into<anyhow::Error>,
+{
+    pub fn open(durable: D) -> Self {
+        Self {
+            cache: HashMap::new(),
+            durable,
+        }
+    }
+
+    fn epoch_events(
+        &mut self,
+        epoch_id: &E::EpochId,
+    ) -> Result<Vec<E>, anyhow::Error> {
+        if let Some(events) = self.cache.get(epoch_id) {
+            return Ok(events.clone());
+        }
+        let events = self.durable.read(epoch_id).map_err(Into::into)?.unwrap_or_default();
+        self.cache.insert(*epoch_id, events.clone());
+        Ok(events)
+    }
+
+    /// Writes `events` for `epoch_id` to the durable store, then applies
+    /// `policy` to the in-memory cache.
+    pub fn write_with_cache(
+        &mut self,
+        epoch_id: E::EpochId,
+        events: Vec<E>,
+        policy: CacheUpdatePolicy,
+    ) -> Result<(), anyhow::Error> {
+        self.durable.write(epoch_id, events.clone()).map_err(Into::into)?;
+        match policy {
+            CacheUpdatePolicy::Overwrite => {
+                self.cache.insert(epoch_id, events);
+            }
+            CacheUpdatePolicy::Remove => {
+                self.cache.remove(&epoch_id);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<E, D> Default for WriteThroughEventStorage<E, D>
+where
+    E: Event,
+    D: Default,
+{
+    fn default() -> Self {
+        Self {
+            cache: HashMap::new(),
+            durable: D::default(),
+        }
+    }
+}
+
+impl<E, D> EventStorage for WriteThroughEventStorage<E, D>
+where
+    E: Event + Clone,
+    D: Readable<E::EpochId, Vec<E>> + Writable<E::EpochId, Vec<E>> + Default,
+    <D as Readable<E::EpochId, Vec<E>>>::Error: Into<anyhow::Error>,
+    <D as Writable<E::EpochId, Vec<E>>>::Error: Into<anyhow::Error>,
+{
+    type Event = E;
+    type Error = anyhow::Error;
+
+    fn add_event(&mut self, event: E) -> Result<(), Self::Error> {
+        let epoch_id = event.epoch_id();
+        let mut events = self.epoch_events(&epoch_id)?;
+        events.push(event);
+        self.write_with_cache(epoch_id, events, CacheUpdatePolicy::Overwrite)
+    }
+
+    fn events_for_epoch(
+        &mut self,
+        epoch_id: &E::EpochId,
+    ) -> Result<impl Iterator<Item = Self::Event>, Self::Error> {
+        Ok(self.epoch_events(epoch_id)?.into_iter())
+    }
+
+    fn events_for_epoch_paged(
+        &mut self,
+        epoch_id: &E::EpochId,
+        page_cursor: PageCursor,
+        page_size: usize,
+    ) -> Result<(Vec<Self::Event>, PageCursor), Self::Error> {
+        let page: Vec<E> = self
+            .epoch_events(epoch_id)?
+            .into_iter()
+            .skip(page_cursor)
+            .take(page_size)
+            .collect();
+        let next_cursor = page_cursor + page.len();
+        Ok((page, next_cursor))
+    }
+
+    fn prune_before(
+        &mut self,
+        is_stale: impl Fn(&E::EpochId) -> bool,
+    ) -> Result<usize, Self::Error> {
+        let stale: Vec<E::EpochId> =
+            self.cache.keys().filter(|epoch_id| is_stale(epoch_id)).copied().collect();
+
+        for epoch_id in &stale {
+            self.durable.delete(epoch_id).map_err(Into::into)?;
+            self.cache.remove(epoch_id);
+        }
+
+        Ok(stale.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        events::{event_key::EventKey, simple_event::SimpleEvent, traits::EventUris},
+        util::persistence::InMemoryKv,
+    };
+
+    type TestStorage =
+        WriteThroughEventStorage<SimpleEvent, InMemoryKv<u64, Vec<SimpleEvent>>>;
+
+    fn mock_event(id: u64, epoch_number: u64) -> SimpleEvent {
+        SimpleEvent {
+            id,
+            epoch_number,
+            event_key: EventKey::default(),
+            uris: EventUris::mock(),
+        }
+    }
+
+    #[test]
+    fn test_add_event_durably_persists_across_reopen() -> Result<(), anyhow::Error> {
+        let mut storage = TestStorage::default();
+        storage.add_event(mock_event(1, 1))?;
+
+        let durable = std::mem::take(&mut storage.durable);
+        let mut reopened = WriteThroughEventStorage::open(durable);
+        let events: Vec<_> = reopened.events_for_epoch(&1)?.collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].id, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_prune_before_removes_from_durable_store_too() -> Result<(), anyhow::Error> {
+        let mut storage = TestStorage::default();
+        storage.add_event(mock_event(1, 1))?;
+
+        let dropped = storage.prune_before(|epoch_id| *epoch_id < 2)?;
+        assert_eq!(dropped, 1);
+
+        let durable = std::mem::take(&mut storage.durable);
+        let mut reopened = WriteThroughEventStorage::open(durable);
+        let events: Vec<_> = reopened.events_for_epoch(&1)?.collect();
+        assert!(events.is_empty());
+        Ok(())
+    }
+}
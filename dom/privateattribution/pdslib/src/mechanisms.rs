@@ -0,0 +1,82 @@
+/// Which norm to use when computing a report's individual sensitivity.
+/// `Laplace` noise is calibrated to L1 sensitivity, `Gaussian` noise to L2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormType {
+    L1,
+    L2,
+}
+
+/// The noise mechanism and scale a report request wants, returned by
+/// `EpochReportRequest::noise_scale`. Determines both which `NormType` the
+/// request's sensitivity should be computed with, and how budget deduction
+/// turns that sensitivity into a privacy loss (see
+/// `pds::accounting::compute_epoch_loss`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NoiseScale {
+    /// Laplace mechanism, pure ε-DP: `scale = L1_sensitivity / epsilon`.
+    Laplace(f64),
+
+    /// Gaussian mechanism, ρ-zCDP: `sigma = L2_sensitivity / sqrt(2 * rho)`
+    /// (see `budget::zcdp_filter::gaussian_rho` for the inverse).
+    Gaussian { sigma: f64 },
+}
+
+impl NoiseScale {
+    /// The norm a report's individual sensitivity must be computed with for
+    /// this mechanism: L1 for Laplace, L2 for Gaussian.
+    pub fn norm_type(&self) -> NormType {
+        match self {
+            Self::Laplace(_) => NormType::L1,
+            Self::Gaussian { .. } => NormType::L2,
+        }
+    }
+}
+
+/// Which mechanism a report request wants, in the terms the requester
+/// controls (an epsilon or a rho) rather than the derived noise scale or
+/// sigma that `EpochReportRequest::noise_scale` needs the report's global
+/// sensitivity to compute.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MechanismChoice {
+    /// Pure ε-DP via the Laplace mechanism.
+    Laplace { requested_epsilon: f64 },
+
+    /// ρ-zCDP via the Gaussian mechanism.
+    Gaussian { requested_rho: f64 },
+}
+
+impl MechanismChoice {
+    /// Hashes this choice, for implementors of
+    /// [`EpochReportRequest::hash_cache_identity`](crate::queries::traits::EpochReportRequest::hash_cache_identity).
+    /// Can't `#[derive(Hash)]` directly since the variants carry an `f64`,
+    /// which isn't `Hash`; hashed via its bits instead; like
+    /// `to_bits`-based hashing elsewhere, `NaN`s with different bit patterns
+    /// hash unequal, but no request ever has a reason to carry a `NaN`
+    /// epsilon/rho.
+    pub fn hash_into(&self, hasher: &mut dyn std::hash::Hasher) {
+        use std::hash::Hash;
+        std::mem::discriminant(self).hash(hasher);
+        match self {
+            Self::Laplace { requested_epsilon } => {
+                requested_epsilon.to_bits().hash(hasher)
+            }
+            Self::Gaussian { requested_rho } => {
+                requested_rho.to_bits().hash(hasher)
+            }
+        }
+    }
+
+    /// Turns this choice into a [`NoiseScale`], given the report's global
+    /// sensitivity (L1 for Laplace, L2 for Gaussian — equal for single-bin
+    /// reports, where this is mainly used so far).
+    pub fn noise_scale(&self, global_sensitivity: f64) -> NoiseScale {
+        match self {
+            Self::Laplace { requested_epsilon } => {
+                NoiseScale::Laplace(global_sensitivity / requested_epsilon)
+            }
+            Self::Gaussian { requested_rho } => NoiseScale::Gaussian {
+                sigma: global_sensitivity / (2.0 * requested_rho).sqrt(),
+            },
+        }
+    }
+}
@@ -5,7 +5,7 @@ use log::debug;
 
 use crate::{
     budget::pure_dp_filter::PureDPBudget,
-    mechanisms::{NoiseScale, NormType},
+    mechanisms::NoiseScale,
     queries::traits::EpochReportRequest,
 };
 
@@ -24,12 +24,14 @@ pub fn compute_epoch_loss<Q: EpochReportRequest>(
         return PureDPBudget::from(0.0);
     }
 
+    let noise_scale = request.noise_scale();
+
     let individual_sensitivity = match num_epochs {
         1 => {
             // Case 2: One epoch.
             request.single_epoch_individual_sensitivity(
                 computed_attribution,
-                NormType::L1,
+                noise_scale.norm_type(),
             )
         }
         _ => {
@@ -40,19 +42,33 @@ pub fn compute_epoch_loss<Q: EpochReportRequest>(
 
     debug!("Individual sensitivity: {individual_sensitivity} for {num_epochs} epochs");
 
-    let NoiseScale::Laplace(noise_scale) = request.noise_scale();
-
     // Treat near-zero noise scales as non-private, i.e. requesting infinite
     // budget, which can only go through if filters are also set to
     // infinite capacity, e.g. for debugging. The machine precision
     // `f64::EPSILON` is not related to privacy.
-    if noise_scale.abs() < f64::EPSILON {
-        return PureDPBudget::from(f64::INFINITY);
+    match noise_scale {
+        NoiseScale::Laplace(scale) => {
+            if scale.abs() < f64::EPSILON {
+                return PureDPBudget::from(f64::INFINITY);
+            }
+            // In Cookie Monster, we have `query_global_sensitivity` /
+            // `requested_epsilon` instead of just `noise_scale`.
+            PureDPBudget::from(individual_sensitivity / scale)
+        }
+        NoiseScale::Gaussian { sigma } => {
+            if sigma.abs() < f64::EPSILON {
+                return PureDPBudget::from(f64::INFINITY);
+            }
+            // The Gaussian mechanism is accounted in rho-zCDP rather than
+            // epsilon, but this function is still hardcoded to return
+            // `PureDPBudget` pending the generic-budget TODO above, so we
+            // store rho in the same f64 slot for now.
+            PureDPBudget::from(crate::budget::zcdp_filter::gaussian_rho(
+                individual_sensitivity,
+                sigma,
+            ))
+        }
     }
-
-    // In Cookie Monster, we have `query_global_sensitivity` /
-    // `requested_epsilon` instead of just `noise_scale`.
-    PureDPBudget::from(individual_sensitivity / noise_scale)
 }
 
 /// Compute the privacy loss at the device-epoch-source level.
@@ -68,7 +84,7 @@ pub fn compute_epoch_source_losses<Q: EpochReportRequest>(
 
     // Collect sources and noise scale from the request.
     let requested_sources = &request.report_uris().source_uris;
-    let NoiseScale::Laplace(noise_scale) = request.noise_scale();
+    let noise_scale = request.noise_scale();
 
     // Count requested sources for case analysis
     let num_requested_sources = requested_sources.len();
@@ -96,7 +112,7 @@ pub fn compute_epoch_source_losses<Q: EpochReportRequest>(
             // epoch-source.
             request.single_epoch_source_individual_sensitivity(
                 computed_attribution,
-                NormType::L1,
+                noise_scale.norm_type(),
             )
         } else {
             // Case 3: Multiple epochs or multiple sources.
@@ -109,17 +125,28 @@ pub fn compute_epoch_source_losses<Q: EpochReportRequest>(
         // are also set to infinite capacity, e.g. for
         // debugging. The machine precision `f64::EPSILON` is
         // not related to privacy.
-        if noise_scale.abs() < f64::EPSILON {
-            per_source_losses
-                .insert(source.clone(), PureDPBudget::from(f64::INFINITY));
-        } else {
+        let loss = match noise_scale {
+            NoiseScale::Laplace(scale) if scale.abs() < f64::EPSILON => {
+                PureDPBudget::from(f64::INFINITY)
+            }
             // In Cookie Monster, we have `query_global_sensitivity` /
             // `requested_epsilon` instead of just `noise_scale`.
-            per_source_losses.insert(
-                source.clone(),
-                PureDPBudget::from(individual_sensitivity / noise_scale),
-            );
-        }
+            NoiseScale::Laplace(scale) => {
+                PureDPBudget::from(individual_sensitivity / scale)
+            }
+            NoiseScale::Gaussian { sigma } if sigma.abs() < f64::EPSILON => {
+                PureDPBudget::from(f64::INFINITY)
+            }
+            // See `compute_epoch_loss` above: rho is stored in the same
+            // f64 slot pending the generic-budget TODO.
+            NoiseScale::Gaussian { sigma } => {
+                PureDPBudget::from(crate::budget::zcdp_filter::gaussian_rho(
+                    individual_sensitivity,
+                    sigma,
+                ))
+            }
+        };
+        per_source_losses.insert(source.clone(), loss);
     }
 
     per_source_losses
@@ -4,7 +4,9 @@ use log::debug;
 
 use super::{
     accounting::{compute_epoch_loss, compute_epoch_source_losses},
+    metrics::{NoopMetricsSink, PdsMetricsSink},
     private_data_service::PdsReport,
+    query_compute_cache::{query_compute_cache_key, QueryComputeCache},
     quotas::{FilterId, PdsFilterStatus},
 };
 use crate::{
@@ -18,6 +20,103 @@ use crate::{
     },
 };
 
+/// How a batch of related requests should share the epoch filters they
+/// contend on, see [`PrivateDataServiceCore::compute_report_batch`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchPolicy {
+    /// If any request in the batch would drive any shared filter out of
+    /// budget, none of the requests consume budget.
+    Atomic,
+
+    /// Process requests in order, letting later requests see budget already
+    /// consumed by earlier ones in the same batch.
+    GreedyOrdered,
+}
+
+/// Whether `compute_report` should fold the budget deduction for every
+/// intermediary report into the single cross-report optimization, or deduct
+/// once per report as usual. Returned by [`OptimizationPolicy::decide`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptimizationDecision {
+    Optimize,
+    PerReport,
+}
+
+/// Injectable replacement for the old hardcoded heuristic ("optimize iff
+/// `site_to_report_mapping` has at least 3 keys") that used to gate the
+/// cross-report budget optimization. Thresholds are supplied at
+/// construction via [`PrivateDataServiceCore::with_optimization_policy`],
+/// analogous to other tunable re-org thresholds in this crate, so the
+/// optimization can be audited and adjusted without editing library
+/// internals.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OptimizationPolicy {
+    /// Minimum number of distinct intermediary URIs in the request's
+    /// `ReportRequestUris` before the optimization is even considered.
+    pub min_intermediaries: usize,
+
+    /// If true, only optimize when the beneficiary (the request's first
+    /// querier URI) is not itself one of the intermediaries, so a
+    /// beneficiary acting as its own intermediary never gets folded into
+    /// the shared-deduction batch.
+    pub require_distinct_beneficiary: bool,
+}
+
+impl Default for OptimizationPolicy {
+    fn default() -> Self {
+        // Matches the old hardcoded heuristic: >= 3 keys in
+        // site_to_report_mapping meant at least 2 intermediary reports,
+        // since the main report is mapped only to the first querier URI.
+        Self {
+            min_intermediaries: 2,
+            require_distinct_beneficiary: false,
+        }
+    }
+}
+
+impl OptimizationPolicy {
+    /// Decides whether to run the cross-report optimization for this
+    /// request. `site_to_report_mapping` is the per-site reports already
+    /// computed for the request (letting this distinguish "main report
+    /// mapped to the first querier only" from genuine multi-intermediary
+    /// fan-out), and `opts_out` is the request's own explicit override
+    /// (see [`EpochReportRequest::opts_out_of_cross_report_optimization`]),
+    /// which always forces [`OptimizationDecision::PerReport`].
+    pub fn decide<U: Eq + std::hash::Hash, R>(
+        &self,
+        site_to_report_mapping: &HashMap<U, R>,
+        uris: &ReportRequestUris<U>,
+        opts_out: bool,
+    ) -> OptimizationDecision {
+        if opts_out {
+            return OptimizationDecision::PerReport;
+        }
+
+        if uris.intermediary_uris.len() < self.min_intermediaries {
+            return OptimizationDecision::PerReport;
+        }
+
+        if self.require_distinct_beneficiary {
+            let beneficiary = uris.querier_uris.first();
+            let beneficiary_is_intermediary = beneficiary
+                .is_some_and(|b| uris.intermediary_uris.contains(b));
+            if beneficiary_is_intermediary {
+                return OptimizationDecision::PerReport;
+            }
+        }
+
+        // The main report is always mapped to the first querier URI alone,
+        // so a single entry here means no intermediary reports were
+        // actually produced yet, regardless of how many intermediary URIs
+        // were requested.
+        if site_to_report_mapping.len() <= 1 {
+            return OptimizationDecision::PerReport;
+        }
+
+        OptimizationDecision::Optimize
+    }
+}
+
 pub struct PrivateDataServiceCore<Q, FS, ERR>
 where
     Q: EpochReportRequest,
@@ -31,6 +130,22 @@ where
     /// Filter storage interface.
     pub filter_storage: FS,
 
+    /// Decides when `compute_report` should run the cross-report budget
+    /// optimization. Defaults to [`OptimizationPolicy::default`]; override
+    /// via [`Self::with_optimization_policy`].
+    pub optimization_policy: OptimizationPolicy,
+
+    /// Memoizes `request.compute_report` results, keyed by a fingerprint of
+    /// the request and the `RelevantEvents` it ran against. Defaults to
+    /// [`QueryComputeCache::default`]; override its capacity via
+    /// [`Self::with_query_compute_cache_capacity`].
+    query_compute_cache: QueryComputeCache<Q::Uri, Q::Report>,
+
+    /// Observer notified of budget-consumption and epoch-drop events.
+    /// Defaults to [`NoopMetricsSink`]; override via
+    /// [`Self::with_metrics_sink`].
+    metrics: Box<dyn PdsMetricsSink<Q::EpochId, FilterId<Q::EpochId, Q::Uri>, Q::Uri>>,
+
     /// This PhantomData serves two purposes:
     /// 1. It Defines the Q and ERR generics on the struct instead of on each
     ///    individual function, reducing boilerplate
@@ -48,18 +163,77 @@ where
         // TODO(https://github.com/columbia/pdslib/issues/21): generic budget
         Budget = PureDPBudget,
     >,
+    FS::Filter: Clone,
     ERR: From<FS::Error>,
 {
     pub fn new(filter_storage: FS) -> Self {
         Self {
             filter_storage,
+            optimization_policy: OptimizationPolicy::default(),
+            query_compute_cache: QueryComputeCache::default(),
+            metrics: Box::new(NoopMetricsSink),
             _phantom: PhantomData,
         }
     }
 
+    /// Overrides the policy deciding when the cross-report optimization
+    /// fires.
+    pub fn with_optimization_policy(
+        mut self,
+        optimization_policy: OptimizationPolicy,
+    ) -> Self {
+        self.optimization_policy = optimization_policy;
+        self
+    }
+
+    /// Overrides how many entries the query-compute cache holds before it
+    /// starts evicting the least-recently-used one. A capacity of 0
+    /// disables the cache.
+    pub fn with_query_compute_cache_capacity(mut self, capacity: usize) -> Self {
+        self.query_compute_cache = QueryComputeCache::new(capacity);
+        self
+    }
+
+    /// Overrides the metrics sink notified of budget-consumption and
+    /// epoch-drop events. Defaults to [`NoopMetricsSink`].
+    pub fn with_metrics_sink(
+        mut self,
+        metrics: impl PdsMetricsSink<Q::EpochId, FilterId<Q::EpochId, Q::Uri>, Q::Uri>
+            + 'static,
+    ) -> Self {
+        self.metrics = Box::new(metrics);
+        self
+    }
+
+    /// Runs `request.compute_report(relevant_events)`, or returns a cached
+    /// result if an identical computation (same request identity, same
+    /// `relevant_events` fingerprint) already ran.
+    fn compute_report_cached(
+        &mut self,
+        request: &Q,
+        relevant_events: &RelevantEvents<Q::Event>,
+    ) -> QueryComputeResult<Q::Uri, R> {
+        let key =
+            query_compute_cache_key(request, relevant_events.fingerprint());
+
+        if let Some(cached) = self.query_compute_cache.get(key) {
+            return cached.clone();
+        }
+
+        let result = request.compute_report(relevant_events);
+        self.query_compute_cache.insert(key, result.clone());
+        result
+    }
+
     /// Computes a report for the given report request.
     /// This function follows `compute_attribution_report` from the Cookie
     /// Monster Algorithm (https://arxiv.org/pdf/2405.16719, Code Listing 1)
+    ///
+    /// Supports multiple querier URIs (beneficiaries) in the same request:
+    /// each gets its own `Nc` non-collusion filter and its own slice of the
+    /// computed report, while the device-epoch-wide `C`/`QTrigger` filters
+    /// are consumed once per epoch, atomically alongside every querier's
+    /// `Nc` filter, so a single report never partially deducts budget.
     pub fn compute_report(
         &mut self,
         request: &Q,
@@ -69,22 +243,12 @@ where
         debug!("Computing report for request {:?}", request);
 
         let uris = request.report_uris();
-
-        // Check if this is a multi-beneficiary query, which we don't support
-        // yet
-        if uris.querier_uris.len() > 1 {
-            unimplemented!("Multi-beneficiary queries");
-        }
-        let querier_uri = uris
-            .querier_uris
-            .first()
-            .expect("Need at least one querier URI");
-
         let epochs = request.epoch_ids();
         let num_epochs = epochs.len();
 
         // Compute the raw report, useful for debugging and accounting.
-        let unfiltered_result = request.compute_report(&relevant_events);
+        let unfiltered_result =
+            self.compute_report_cached(request, &relevant_events);
 
         // Browse epochs in the attribution window
         let mut oob_filters = vec![];
@@ -92,115 +256,333 @@ where
             // Step 1. Get relevant events for the current epoch `epoch_id`.
             let epoch_relevant_events = relevant_events.for_epoch(&epoch_id);
 
-            // Step 2. Compute individual loss for current epoch.
-            let individual_privacy_loss = compute_epoch_loss(
+            // Step 2 & 3. Compute, for every querier, its own individual
+            // loss and device-epoch-source losses for this epoch.
+            let (losses_by_querier, source_losses) = self.epoch_losses(
                 request,
                 epoch_relevant_events,
-                unfiltered_result.uri_report_map.get(querier_uri).unwrap(),
-                num_epochs,
-            );
-
-            // Step 3. Compute device-epoch-source losses.
-            let source_losses = compute_epoch_source_losses(
-                request,
                 relevant_events.sources_for_epoch(&epoch_id),
-                unfiltered_result.uri_report_map.get(querier_uri).unwrap(),
+                &unfiltered_result,
+                &uris,
                 num_epochs,
             );
 
             // Step 4. Try to consume budget from current epoch, drop events if
-            // OOB. Two phase commit.
+            // OOB. Atomic, all-or-nothing across every filter touched by
+            // this epoch, including every querier's `Nc` filter.
             let filters_to_consume = self.filters_to_consume(
                 epoch_id,
-                &individual_privacy_loss,
+                &losses_by_querier,
                 &source_losses,
-                request.report_uris(),
+                &uris,
             );
 
-            // Phase 1: dry run.
-            let check_status = self.deduct_budget(
-                &filters_to_consume,
-                true, // dry run
-            )?;
-
-            match check_status {
-                PdsFilterStatus::Continue => {
-                    // Phase 2: Consume the budget
-                    let consume_status = self.deduct_budget(
-                        &filters_to_consume,
-                        false, // actually consume
-                    )?;
-
-                    if consume_status != PdsFilterStatus::Continue {
-                        panic!("ERR: Phase 2 failed unexpectedly wtih status {consume_status:?} after Phase 1 succeeded");
-                    }
-                }
+            match self.deduct_budget(&filters_to_consume)? {
+                PdsFilterStatus::Continue => {}
 
                 PdsFilterStatus::OutOfBudget(mut filters) => {
                     // Not enough budget, drop events without any filter
                     // consumption
                     relevant_events.drop_epoch(&epoch_id);
 
+                    self.metrics.on_epoch_dropped(epoch_id, &filters);
+
                     // Keep track of why we dropped this epoch
                     oob_filters.append(&mut filters);
                 }
             }
         }
 
-        // Now that we've dropped OOB epochs, we can compute the final report.
-        let filtered_result = request.compute_report(&relevant_events);
-
-        let filtered_report =
-            filtered_result.uri_report_map.get(querier_uri).unwrap();
-        let unfiltered_report =
-            unfiltered_result.uri_report_map.get(querier_uri).unwrap();
+        // Now that we've dropped OOB epochs, we can compute the final
+        // report. If no epoch was dropped, `relevant_events.fingerprint()`
+        // is unchanged and this reuses `unfiltered_result` from the cache.
+        let filtered_result =
+            self.compute_report_cached(request, &relevant_events);
+
+        // Build one report per querier URI. Each report only lists the
+        // `oob_filters` relevant to it (its own `Nc` filter plus the shared
+        // `C`/`QTrigger`/`QSource` ones), so an epoch dropped because one
+        // querier's `Nc` filter ran out doesn't falsely blame another
+        // querier's filter in its report.
+        let mut reports = HashMap::new();
+        for querier_uri in &uris.querier_uris {
+            let filtered_report =
+                filtered_result.uri_report_map.get(querier_uri).unwrap();
+            let unfiltered_report =
+                unfiltered_result.uri_report_map.get(querier_uri).unwrap();
+
+            let main_report = PdsReport {
+                filtered_report: filtered_report.clone(),
+                unfiltered_report: unfiltered_report.clone(),
+                oob_filters: Self::filters_relevant_to_querier(
+                    &oob_filters,
+                    querier_uri,
+                ),
+            };
 
-        let main_report = PdsReport {
-            filtered_report: filtered_report.clone(),
-            unfiltered_report: unfiltered_report.clone(),
-            oob_filters,
-        };
+            self.metrics.on_report_computed(querier_uri, num_epochs);
+            reports.insert(querier_uri.clone(), main_report);
+        }
 
         // Handle optimization queries when at least two intermediary URIs are
         // in the request.
-        if self.is_optimization_query(&filtered_result.uri_report_map) {
+        let optimization_decision = self.optimization_policy.decide(
+            &filtered_result.uri_report_map,
+            &uris,
+            request.opts_out_of_cross_report_optimization(),
+        );
+        if optimization_decision == OptimizationDecision::Optimize {
             let intermediate_reports = self.calculate_optimization_query(
                 request,
                 unfiltered_result,
                 filtered_result,
-                main_report.oob_filters,
+                oob_filters,
             )?;
             return Ok(intermediate_reports);
         }
 
         // For regular requests or optimization queries without intermediary
         // reports
+        Ok(reports)
+    }
+
+    /// For every querier URI in `uris`, computes its own individual privacy
+    /// loss (via `compute_epoch_loss`, run against that querier's own slice
+    /// of `unfiltered_result`) for this epoch, plus the per-source losses
+    /// combined across queriers. Per-source (and, in `filters_to_consume`,
+    /// the device-epoch-wide `C`/`QTrigger`) losses take the maximum across
+    /// queriers: those filters are shared and consumed once per epoch no
+    /// matter how many queriers the report serves, so the maximum is the
+    /// smallest value that's still a sound upper bound for all of them.
+    #[allow(clippy::type_complexity)]
+    fn epoch_losses<'a>(
+        &mut self,
+        request: &Q,
+        epoch_relevant_events: &[Q::Event],
+        epoch_event_sources: std::collections::HashSet<&'a Q::Uri>,
+        unfiltered_result: &QueryComputeResult<Q::Uri, R>,
+        uris: &ReportRequestUris<Q::Uri>,
+        num_epochs: usize,
+    ) -> (HashMap<Q::Uri, PureDPBudget>, HashMap<Q::Uri, PureDPBudget>) {
+        let mut losses_by_querier = HashMap::new();
+        let mut source_losses: HashMap<Q::Uri, PureDPBudget> = HashMap::new();
+
+        for querier_uri in &uris.querier_uris {
+            let querier_unfiltered_report =
+                unfiltered_result.uri_report_map.get(querier_uri).unwrap();
+
+            let individual_privacy_loss = compute_epoch_loss(
+                request,
+                epoch_relevant_events,
+                querier_unfiltered_report,
+                num_epochs,
+            );
+            self.metrics.on_individual_sensitivity(individual_privacy_loss);
+            losses_by_querier.insert(querier_uri.clone(), individual_privacy_loss);
+
+            let querier_source_losses = compute_epoch_source_losses(
+                request,
+                epoch_event_sources.clone(),
+                querier_unfiltered_report,
+                num_epochs,
+            );
+            for (source, loss) in querier_source_losses {
+                self.metrics.on_individual_sensitivity(loss);
+                source_losses
+                    .entry(source)
+                    .and_modify(|existing| {
+                        if loss > *existing {
+                            *existing = loss;
+                        }
+                    })
+                    .or_insert(loss);
+            }
+        }
+
+        (losses_by_querier, source_losses)
+    }
+
+    /// Filters `oob_filters` down to the ones relevant to `querier_uri`: its
+    /// own `Nc` filter, plus every shared (`C`, `QTrigger`, `QSource`)
+    /// filter, which apply regardless of querier.
+    fn filters_relevant_to_querier(
+        oob_filters: &[FilterId<Q::EpochId, Q::Uri>],
+        querier_uri: &Q::Uri,
+    ) -> Vec<FilterId<Q::EpochId, Q::Uri>> {
+        oob_filters
+            .iter()
+            .filter(|filter_id| match filter_id {
+                FilterId::Nc(_, uri) => uri == querier_uri,
+                FilterId::C(_)
+                | FilterId::QTrigger(_, _)
+                | FilterId::QSource(_, _) => true,
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Evaluates a set of related requests against the same epoch filters.
+    ///
+    /// `GreedyOrdered` simply calls `compute_report` for each request in
+    /// order. `Atomic` first accumulates the union of `filters_to_consume`
+    /// across every request and epoch in the batch, summing losses for
+    /// filters shared across requests (`C` and `QSource` in particular), so
+    /// the dry-run check reflects cross-request contention that a caller
+    /// looping over `compute_report` would otherwise overrun. If the
+    /// combined dry run succeeds, the whole batch commits in one step and no
+    /// request drops any epoch; if it fails, none of the requests consume
+    /// budget and every request is reported as fully dropped.
+    pub fn compute_report_batch(
+        &mut self,
+        requests: &[(Q, RelevantEvents<Q::Event>)],
+        policy: BatchPolicy,
+    ) -> Result<Vec<HashMap<Q::Uri, PdsReport<Q>>>, ERR> {
+        match policy {
+            BatchPolicy::GreedyOrdered => requests
+                .iter()
+                .map(|(request, relevant_events)| {
+                    self.compute_report(request, relevant_events.clone())
+                })
+                .collect(),
+
+            BatchPolicy::Atomic => {
+                let mut combined: HashMap<
+                    FilterId<Q::EpochId, Q::Uri>,
+                    PureDPBudget,
+                > = HashMap::new();
+
+                for (request, relevant_events) in requests {
+                    let uris = request.report_uris();
+                    let unfiltered_result =
+                        self.compute_report_cached(request, relevant_events);
+                    let num_epochs = request.epoch_ids().len();
+
+                    for epoch_id in request.epoch_ids() {
+                        let epoch_relevant_events =
+                            relevant_events.for_epoch(&epoch_id);
+                        let (losses_by_querier, source_losses) = self
+                            .epoch_losses(
+                                request,
+                                epoch_relevant_events,
+                                relevant_events.sources_for_epoch(&epoch_id),
+                                &unfiltered_result,
+                                &uris,
+                                num_epochs,
+                            );
+                        let filters_to_consume = self.filters_to_consume(
+                            epoch_id,
+                            &losses_by_querier,
+                            &source_losses,
+                            &uris,
+                        );
+                        for (fid, loss) in filters_to_consume {
+                            *combined.entry(fid).or_insert(0.0) += *loss;
+                        }
+                    }
+                }
+
+                let combined_refs: HashMap<_, _> = combined
+                    .iter()
+                    .map(|(fid, loss)| (fid.clone(), loss))
+                    .collect();
+
+                match self.deduct_budget(&combined_refs)? {
+                    PdsFilterStatus::Continue => {
+                        requests
+                            .iter()
+                            .map(|(request, relevant_events)| {
+                                self.build_report_without_deduction(
+                                    request,
+                                    relevant_events,
+                                )
+                            })
+                            .collect()
+                    }
+                    PdsFilterStatus::OutOfBudget(oob_filters) => requests
+                        .iter()
+                        .map(|(request, relevant_events)| {
+                            let mut dropped_events = relevant_events.clone();
+                            for epoch_id in request.epoch_ids() {
+                                dropped_events.drop_epoch(&epoch_id);
+                            }
+                            let mut report = self
+                                .build_report_without_deduction(
+                                    request,
+                                    &dropped_events,
+                                )?;
+                            for pds_report in report.values_mut() {
+                                pds_report.oob_filters = oob_filters.clone();
+                            }
+                            Ok(report)
+                        })
+                        .collect(),
+                }
+            }
+        }
+    }
+
+    /// Builds a report assuming every epoch in `relevant_events` is kept,
+    /// i.e. without running (or re-running) the two-phase budget check.
+    /// Used by [`Self::compute_report_batch`] once a batch-wide deduction
+    /// has already been committed (or rejected) for the whole set of
+    /// requests.
+    fn build_report_without_deduction(
+        &mut self,
+        request: &Q,
+        relevant_events: &RelevantEvents<Q::Event>,
+    ) -> Result<HashMap<Q::Uri, PdsReport<Q>>, ERR> {
+        let uris = request.report_uris();
+        let querier_uri = uris
+            .querier_uris
+            .first()
+            .expect("Need at least one querier URI");
+
+        let result = self.compute_report_cached(request, relevant_events);
+        let report = result.uri_report_map.get(querier_uri).unwrap();
+
+        let main_report = PdsReport {
+            filtered_report: report.clone(),
+            unfiltered_report: report.clone(),
+            oob_filters: vec![],
+        };
+
         Ok(HashMap::from([(querier_uri.clone(), main_report)]))
     }
 
-    /// Calculate how much privacy to deduct from which filters,
-    /// for the given epoch and losses.
+    /// Calculate how much privacy to deduct from which filters, for the
+    /// given epoch and losses. Each querier gets its own `Nc` filter,
+    /// debited with its own loss; the shared `C` and `QTrigger` filters are
+    /// debited once, with the maximum loss across every querier in
+    /// `losses_by_querier`, since a single atomic deduction can't partially
+    /// consume a filter shared by several queriers.
     pub fn filters_to_consume<'a>(
         &self,
         epoch_id: Q::EpochId,
-        loss: &'a FS::Budget,
+        losses_by_querier: &'a HashMap<Q::Uri, FS::Budget>,
         source_losses: &'a HashMap<Q::Uri, FS::Budget>,
         uris: &ReportRequestUris<Q::Uri>,
     ) -> HashMap<FilterId<Q::EpochId, Q::Uri>, &'a PureDPBudget> {
-        // Build the filter IDs for NC, C and QTrigger
-        let mut device_epoch_filter_ids = Vec::new();
+        let mut filters_to_consume = HashMap::new();
+
+        // Each querier has its own NC filter, debited with its own loss.
         for query_uri in &uris.querier_uris {
-            device_epoch_filter_ids
-                .push(FilterId::Nc(epoch_id, query_uri.clone()));
+            if let Some(loss) = losses_by_querier.get(query_uri) {
+                filters_to_consume
+                    .insert(FilterId::Nc(epoch_id, query_uri.clone()), loss);
+            }
         }
-        device_epoch_filter_ids
-            .push(FilterId::QTrigger(epoch_id, uris.trigger_uri.clone()));
-        device_epoch_filter_ids.push(FilterId::C(epoch_id));
 
-        // NC, C and QTrigger all have the same device-epoch level loss
-        let mut filters_to_consume = HashMap::new();
-        for filter_id in device_epoch_filter_ids {
-            filters_to_consume.insert(filter_id, loss);
+        // C and QTrigger are shared across queriers: debit the maximum loss
+        // across them, a sound upper bound on what any single querier needs.
+        if let Some(shared_loss) = losses_by_querier
+            .values()
+            .max_by(|a, b| a.partial_cmp(b).unwrap())
+        {
+            filters_to_consume.insert(
+                FilterId::QTrigger(epoch_id, uris.trigger_uri.clone()),
+                shared_loss,
+            );
+            filters_to_consume.insert(FilterId::C(epoch_id), shared_loss);
         }
 
         // Add the QSource filters with their own device-epoch-source level loss
@@ -212,7 +594,12 @@ where
         filters_to_consume
     }
 
-    /// Deduct the privacy loss from the various filters.
+    /// Deduct the privacy loss from the various filters, atomically: either
+    /// every filter in `filters_to_consume` gets debited, or (if any of
+    /// them is out of budget) none of them do. Uses a `FilterTransaction`
+    /// instead of the old hand-rolled two-phase commit (a `can_consume` dry
+    /// run over every filter, then a `try_consume` pass that was expected,
+    /// but not guaranteed, to agree with it).
     #[allow(clippy::type_complexity)]
     pub fn deduct_budget(
         &mut self,
@@ -220,43 +607,36 @@ where
             FilterId<Q::EpochId, Q::Uri>,
             &PureDPBudget,
         >,
-        dry_run: bool,
     ) -> Result<PdsFilterStatus<FilterId<Q::EpochId, Q::Uri>>, ERR> {
-        // Try to consume the privacy loss from the filters
+        let mut txn = self.filter_storage.begin_transaction();
         let mut oob_filters = vec![];
-        for (fid, loss) in filters_to_consume {
-            let filter_status = match dry_run {
-                true => self.filter_storage.can_consume(fid, loss)?,
-                false => self.filter_storage.try_consume(fid, loss)?,
-            };
 
+        for (fid, loss) in filters_to_consume {
+            let filter_status =
+                self.filter_storage.try_consume_in(&mut txn, fid, loss)?;
             if filter_status == FilterStatus::OutOfBudget {
                 oob_filters.push(fid.clone());
             }
         }
 
-        // If any filter was out of budget, the whole operation is marked as out
-        // of budget.
+        // If any filter was out of budget, roll back and mark the whole
+        // operation as out of budget.
         if !oob_filters.is_empty() {
+            self.filter_storage.rollback(txn);
             return Ok(PdsFilterStatus::OutOfBudget(oob_filters));
         }
-        Ok(PdsFilterStatus::Continue)
-    }
 
-    fn is_optimization_query(
-        &self,
-        site_to_report_mapping: &HashMap<Q::Uri, Q::Report>,
-    ) -> bool {
-        // TODO: May need to change this based on assumption changes.
-        // If the mapping has more then 3 keys, that means it has at least 2
-        // intermediary sites (since we map the main report only to the first
-        // querier URI), so this would be the case where the query optimization
-        // can take place.
-        if site_to_report_mapping.keys().len() >= 3 {
-            return true;
+        self.filter_storage.commit(txn)?;
+
+        let mut total_epsilon = 0.0;
+        for (fid, loss) in filters_to_consume {
+            let remaining = self.filter_storage.remaining_budget(fid)?;
+            self.metrics.on_budget_consumed(fid, **loss, remaining);
+            total_epsilon += **loss;
         }
+        self.metrics.on_epsilon_consumed(total_epsilon);
 
-        false
+        Ok(PdsFilterStatus::Continue)
     }
 
     fn calculate_optimization_query(
@@ -3,13 +3,16 @@
 use std::{collections::HashMap, fmt::Debug, hash::Hash, vec};
 
 use log::debug;
-use serde::{ser::SerializeStruct, Serialize};
+use serde::{Deserialize, Serialize};
 
 use crate::{
     budget::{
         hashmap_filter_storage::HashMapFilterStorage,
         pure_dp_filter::{PureDPBudget, PureDPBudgetFilter},
-        traits::{Budget, FilterCapacities, FilterStatus, FilterStorage},
+        traits::{
+            Budget, FilterCapacities, FilterStatus, FilterStorage,
+            FilterUtilization,
+        },
     },
     events::traits::{
         EpochEvents, EpochId, Event, EventStorage, RelevantEventSelector,
@@ -35,8 +38,33 @@ pub enum FilterId<
     QSource(E, U /* source URI */),
 }
 
+/// One filter's state in a [`FilterStorageSnapshot`], keyed by its full
+/// `FilterId` so a snapshot is symmetric: unlike the old bucketed
+/// `ncs`/`cs`/`qtriggers`/`qsources` format, which only grouped filters by
+/// kind and dropped their ids, this can be loaded straight back into a
+/// `HashMapFilterStorage`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilterSnapshotEntry<E, U> {
+    pub filter_id: FilterId<E, U>,
+    pub consumed: PureDPBudget,
+
+    /// `None` for a filter with infinite/unset capacity. Skipped on
+    /// serialization so a snapshot of mostly unlimited filters stays
+    /// compact.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub capacity: Option<PureDPBudget>,
+}
+
+/// Self-describing, round-trippable snapshot of a filter storage's full
+/// state, suitable for a browser client to checkpoint and restore
+/// per-epoch budget across sessions.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FilterStorageSnapshot<E, U> {
+    pub filters: Vec<FilterSnapshotEntry<E, U>>,
+}
+
 // TODO: generic budget and filter?
-impl<E, U> Serialize
+impl<E: Clone, U: Clone> Serialize
     for HashMapFilterStorage<
         FilterId<E, U>,
         PureDPBudgetFilter,
@@ -48,28 +76,53 @@ impl<E, U> Serialize
     where
         S: serde::Serializer,
     {
-        let mut ncs = vec![];
-        let mut cs = vec![];
-        let mut qtriggers = vec![];
-        let mut qsources = vec![];
-
-        for (filter_id, filter) in &self.filters {
-            match filter_id {
-                FilterId::Nc(_, _) => ncs.push(filter),
-                FilterId::C(_) => cs.push(filter),
-                FilterId::QTrigger(_, _) => qtriggers.push(filter),
-                FilterId::QSource(_, _) => qsources.push(filter),
-            }
-        }
+        self.export_snapshot().serialize(serializer)
+    }
+}
+
+impl<E, U> HashMapFilterStorage<
+    FilterId<E, U>,
+    PureDPBudgetFilter,
+    PureDPBudget,
+    StaticCapacities<FilterId<E, U>, PureDPBudget>,
+>
+where
+    E: Clone,
+    U: Clone,
+{
+    /// Exports every known filter's id and state into a self-describing,
+    /// round-trippable snapshot.
+    pub fn export_snapshot(&self) -> FilterStorageSnapshot<E, U> {
+        let filters = self
+            .filters
+            .iter()
+            .map(|(filter_id, filter)| FilterSnapshotEntry {
+                filter_id: filter_id.clone(),
+                consumed: filter.consumed,
+                capacity: filter.capacity,
+            })
+            .collect();
+        FilterStorageSnapshot { filters }
+    }
 
-        // Serialize the vectors into the desired format
-        let mut state =
-            serializer.serialize_struct("HashMapFilterStorage", 4)?;
-        state.serialize_field("ncs", &ncs)?;
-        state.serialize_field("cs", &cs)?;
-        state.serialize_field("qtriggers", &qtriggers)?;
-        state.serialize_field("qsources", &qsources)?;
-        state.end()
+    /// Restores a storage previously produced by [`Self::export_snapshot`].
+    pub fn load_snapshot(
+        capacities: StaticCapacities<FilterId<E, U>, PureDPBudget>,
+        snapshot: FilterStorageSnapshot<E, U>,
+    ) -> Result<Self, anyhow::Error>
+    where
+        E: Eq + Hash + Debug,
+        U: Eq + Hash + Debug,
+    {
+        let mut storage = Self::new(capacities)?;
+        for entry in snapshot.filters {
+            let filter = PureDPBudgetFilter {
+                consumed: entry.consumed,
+                capacity: entry.capacity,
+            };
+            storage.set_filter(&entry.filter_id, filter)?;
+        }
+        Ok(storage)
     }
 }
 
@@ -170,6 +223,348 @@ pub struct PdsReport<Q: EpochReportRequest> {
     pub oob_filters: Vec<FilterId<Q::EpochId, Q::Uri>>,
 }
 
+/// Identifier returned by [`SubscriptionRegistry::subscribe`], used to
+/// unsubscribe later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+/// Which kind of [`FilterId`] a [`SubscriptionFilter::Variant`] matches,
+/// mirroring the variants of [`FilterId`] without the epoch/URI payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FilterIdVariant {
+    Nc,
+    C,
+    QTrigger,
+    QSource,
+}
+
+impl<E, U> From<&FilterId<E, U>> for FilterIdVariant {
+    fn from(filter_id: &FilterId<E, U>) -> Self {
+        match filter_id {
+            FilterId::Nc(..) => FilterIdVariant::Nc,
+            FilterId::C(..) => FilterIdVariant::C,
+            FilterId::QTrigger(..) => FilterIdVariant::QTrigger,
+            FilterId::QSource(..) => FilterIdVariant::QSource,
+        }
+    }
+}
+
+/// Emitted when a filter is pushed into `oob_filters`, or crosses a
+/// subscriber-configured remaining-budget threshold.
+#[derive(Debug, Clone)]
+pub struct FilterDepletionEvent<E, U> {
+    pub epoch_id: E,
+    pub filter_id: FilterId<E, U>,
+    pub remaining_budget: PureDPBudget,
+    pub timestamp: std::time::SystemTime,
+}
+
+/// Which depletion events a subscription wants to hear about.
+pub enum SubscriptionFilter<U> {
+    /// Matches every depletion event.
+    Any,
+
+    /// Matches only events for filters of `variant`, optionally restricted
+    /// to a specific URI (e.g. only `QSource` depletions for a given source
+    /// URI).
+    Variant {
+        variant: FilterIdVariant,
+        uri: Option<U>,
+    },
+}
+
+impl<U: PartialEq> SubscriptionFilter<U> {
+    fn matches<E>(&self, filter_id: &FilterId<E, U>) -> bool {
+        match self {
+            SubscriptionFilter::Any => true,
+            SubscriptionFilter::Variant { variant, uri } => {
+                if FilterIdVariant::from(filter_id) != *variant {
+                    return false;
+                }
+                match (uri, filter_id) {
+                    (None, _) => true,
+                    (Some(want), FilterId::Nc(_, got)) => want == got,
+                    (Some(want), FilterId::QTrigger(_, got)) => want == got,
+                    (Some(want), FilterId::QSource(_, got)) => want == got,
+                    (Some(_), FilterId::C(_)) => false,
+                }
+            }
+        }
+    }
+}
+
+type DepletionCallback<E, U> = Box<dyn FnMut(&FilterDepletionEvent<E, U>)>;
+
+/// Pub-sub registry of filter-depletion subscriptions. Every time a filter
+/// is pushed into `oob_filters`, or crosses a configured remaining-budget
+/// threshold, matching subscribers are notified instead of having to poll
+/// the whole storage.
+#[derive(Default)]
+pub struct SubscriptionRegistry<E, U> {
+    next_id: u64,
+    subscriptions:
+        Vec<(SubscriptionId, SubscriptionFilter<U>, DepletionCallback<E, U>)>,
+}
+
+impl<E, U: PartialEq> SubscriptionRegistry<E, U> {
+    pub fn subscribe(
+        &mut self,
+        filter: SubscriptionFilter<U>,
+        callback: impl FnMut(&FilterDepletionEvent<E, U>) + 'static,
+    ) -> SubscriptionId {
+        self.next_id += 1;
+        let id = SubscriptionId(self.next_id);
+        self.subscriptions.push((id, filter, Box::new(callback)));
+        id
+    }
+
+    pub fn unsubscribe(&mut self, id: SubscriptionId) {
+        self.subscriptions.retain(|(sub_id, _, _)| *sub_id != id);
+    }
+
+    /// Notifies every subscription whose filter matches `event.filter_id`.
+    pub fn emit(&mut self, event: &FilterDepletionEvent<E, U>) {
+        for (_, filter, callback) in &mut self.subscriptions {
+            if filter.matches(&event.filter_id) {
+                callback(event);
+            }
+        }
+    }
+}
+
+/// Wraps an [`EpochPrivateDataService`] with a pub-sub subscription layer
+/// over filter depletion, so embedding applications can throttle queriers
+/// or trigger re-keying in real time instead of only learning about
+/// out-of-budget filters from `PdsReport::oob_filters` after the fact.
+pub struct SubscribableEpochPds<FS, ES, Q, ERR>
+where
+    FS: FilterStorage,
+    ES: EventStorage,
+    Q: EpochReportRequest,
+    ERR: From<FS::Error> + From<ES::Error>,
+{
+    pub inner: EpochPrivateDataService<FS, ES, Q, ERR>,
+
+    /// Remaining-budget threshold below which [`Self::notify_thresholds`]
+    /// emits a depletion event for a filter that hasn't gone fully
+    /// out-of-budget yet. `None` disables threshold notifications.
+    pub threshold: Option<PureDPBudget>,
+
+    subscriptions: SubscriptionRegistry<Q::EpochId, Q::Uri>,
+}
+
+impl<FS, ES, Q, ERR> SubscribableEpochPds<FS, ES, Q, ERR>
+where
+    FS: FilterStorage,
+    ES: EventStorage,
+    Q: EpochReportRequest,
+    ERR: From<FS::Error> + From<ES::Error>,
+{
+    pub fn new(inner: EpochPrivateDataService<FS, ES, Q, ERR>) -> Self {
+        Self {
+            inner,
+            threshold: None,
+            subscriptions: SubscriptionRegistry::default(),
+        }
+    }
+
+    pub fn subscribe(
+        &mut self,
+        filter: SubscriptionFilter<Q::Uri>,
+        callback: impl FnMut(&FilterDepletionEvent<Q::EpochId, Q::Uri>)
+            + 'static,
+    ) -> SubscriptionId
+    where
+        Q::Uri: PartialEq,
+    {
+        self.subscriptions.subscribe(filter, callback)
+    }
+
+    pub fn unsubscribe(&mut self, id: SubscriptionId) {
+        self.subscriptions.unsubscribe(id);
+    }
+
+    /// Notifies subscribers about every filter in `oob_filters`, typically
+    /// the list returned in a [`PdsReport`] after `compute_report`.
+    pub fn notify_oob(
+        &mut self,
+        oob_filters: &[FilterId<Q::EpochId, Q::Uri>],
+    ) where
+        Q::EpochId: Clone,
+        Q::Uri: Clone + PartialEq,
+    {
+        for filter_id in oob_filters {
+            let epoch_id = match filter_id {
+                FilterId::Nc(epoch_id, _)
+                | FilterId::C(epoch_id)
+                | FilterId::QTrigger(epoch_id, _)
+                | FilterId::QSource(epoch_id, _) => epoch_id.clone(),
+            };
+            self.subscriptions.emit(&FilterDepletionEvent {
+                epoch_id,
+                filter_id: filter_id.clone(),
+                remaining_budget: 0.0,
+                timestamp: std::time::SystemTime::now(),
+            });
+        }
+    }
+
+    /// Checks each of `filter_ids` against `self.threshold` and notifies
+    /// subscribers about the ones that have crossed it, even though they
+    /// aren't out-of-budget yet.
+    pub fn notify_thresholds(
+        &mut self,
+        filter_ids: &[FilterId<Q::EpochId, Q::Uri>],
+    ) -> Result<(), ERR>
+    where
+        FS: FilterStorage<
+            FilterId = FilterId<Q::EpochId, Q::Uri>,
+            Budget = PureDPBudget,
+        >,
+        Q::EpochId: Clone,
+        Q::Uri: Clone + PartialEq,
+    {
+        let Some(threshold) = self.threshold else {
+            return Ok(());
+        };
+
+        for filter_id in filter_ids {
+            let remaining_budget =
+                self.inner.filter_storage.remaining_budget(filter_id)?;
+            if remaining_budget <= threshold {
+                let epoch_id = match filter_id {
+                    FilterId::Nc(epoch_id, _)
+                    | FilterId::C(epoch_id)
+                    | FilterId::QTrigger(epoch_id, _)
+                    | FilterId::QSource(epoch_id, _) => epoch_id.clone(),
+                };
+                self.subscriptions.emit(&FilterDepletionEvent {
+                    epoch_id,
+                    filter_id: filter_id.clone(),
+                    remaining_budget,
+                    timestamp: std::time::SystemTime::now(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Cumulative count of `PdsFilterStatus::OutOfBudget` outcomes, broken down
+/// by which [`FilterIdVariant`] caused the drop.
+#[derive(Debug, Clone, Default)]
+pub struct PdsMetrics {
+    oob_counts: HashMap<FilterIdVariant, u64>,
+}
+
+impl PdsMetrics {
+    /// Records that every filter in `oob_filters` (typically
+    /// `PdsReport::oob_filters`) caused an atomic check to fail.
+    pub fn record_oob<E, U>(&mut self, oob_filters: &[FilterId<E, U>]) {
+        for filter_id in oob_filters {
+            *self
+                .oob_counts
+                .entry(FilterIdVariant::from(filter_id))
+                .or_insert(0) += 1;
+        }
+    }
+
+    /// Renders the cumulative OOB counts as Prometheus text exposition
+    /// format, so operators can scrape Nc/C/QTrigger/QSource pressure
+    /// alongside the rest of their metrics.
+    pub fn to_prometheus_text(&self) -> String {
+        let mut lines = vec![
+            "# HELP pdslib_oob_total Cumulative out-of-budget filter hits, by filter kind.".to_string(),
+            "# TYPE pdslib_oob_total counter".to_string(),
+        ];
+        for variant in [
+            FilterIdVariant::Nc,
+            FilterIdVariant::C,
+            FilterIdVariant::QTrigger,
+            FilterIdVariant::QSource,
+        ] {
+            let count = self.oob_counts.get(&variant).copied().unwrap_or(0);
+            lines.push(format!(
+                "pdslib_oob_total{{filter_kind=\"{variant:?}\"}} {count}"
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Wraps an [`EpochPrivateDataService`] with a metrics/introspection layer,
+/// analogous to an admin metrics endpoint: per-filter remaining budget and
+/// utilization, plus cumulative out-of-budget counts by filter kind, so
+/// operators can watch Nc/C/QTrigger/QSource pressure and tune capacities
+/// before queriers start silently losing events.
+pub struct InstrumentedEpochPds<FS, ES, Q, ERR>
+where
+    FS: FilterStorage,
+    ES: EventStorage,
+    Q: EpochReportRequest,
+    ERR: From<FS::Error> + From<ES::Error>,
+{
+    pub inner: EpochPrivateDataService<FS, ES, Q, ERR>,
+    pub metrics: PdsMetrics,
+}
+
+impl<FS, ES, Q, ERR> InstrumentedEpochPds<FS, ES, Q, ERR>
+where
+    FS: FilterStorage,
+    ES: EventStorage,
+    Q: EpochReportRequest,
+    ERR: From<FS::Error> + From<ES::Error>,
+{
+    pub fn new(inner: EpochPrivateDataService<FS, ES, Q, ERR>) -> Self {
+        Self {
+            inner,
+            metrics: PdsMetrics::default(),
+        }
+    }
+
+    /// Records that `oob_filters` (typically `PdsReport::oob_filters`)
+    /// caused this epoch's atomic check to fail, updating the cumulative
+    /// per-filter-kind counters.
+    pub fn record_oob(
+        &mut self,
+        oob_filters: &[FilterId<Q::EpochId, Q::Uri>],
+    ) {
+        self.metrics.record_oob(oob_filters);
+    }
+
+    /// Builds a per-filter remaining-budget/capacity/utilization snapshot
+    /// for `filter_ids`, reusing [`FilterStorage::utilization`].
+    pub fn utilization_snapshot(
+        &mut self,
+        filter_ids: &[FilterId<Q::EpochId, Q::Uri>],
+    ) -> Result<
+        HashMap<FilterId<Q::EpochId, Q::Uri>, FilterUtilization<PureDPBudget>>,
+        ERR,
+    >
+    where
+        FS: FilterStorage<
+            FilterId = FilterId<Q::EpochId, Q::Uri>,
+            Budget = PureDPBudget,
+        >,
+        Q::EpochId: Clone + Eq + Hash,
+        Q::Uri: Clone + Eq + Hash,
+    {
+        let mut snapshot = HashMap::new();
+        for filter_id in filter_ids {
+            let utilization =
+                self.inner.filter_storage.utilization(filter_id)?;
+            snapshot.insert(filter_id.clone(), utilization);
+        }
+        Ok(snapshot)
+    }
+
+    /// Renders the cumulative out-of-budget counters as Prometheus text
+    /// exposition format.
+    pub fn to_prometheus_text(&self) -> String {
+        self.metrics.to_prometheus_text()
+    }
+}
+
 /// API for the epoch-based PDS.
 ///
 /// TODO(https://github.com/columbia/pdslib/issues/21): support more than PureDP
@@ -181,6 +576,7 @@ where
     E: Event<EpochId = EI, Uri = U> + Clone,
     EE: EpochEvents,
     FS: FilterStorage<Budget = PureDPBudget, FilterId = FilterId<EI, U>>,
+    FS::Filter: Clone,
     RES: RelevantEventSelector<Event = E>,
     ES: EventStorage<
         Event = E,
@@ -207,17 +603,19 @@ where
     /// Computes a report for the given report request.
     /// This function follows `compute_attribution_report` from the Cookie
     /// Monster Algorithm (https://arxiv.org/pdf/2405.16719, Code Listing 1)
+    ///
+    /// Supports multiple querier URIs (beneficiaries) in the same request:
+    /// each gets its own `Nc` non-collusion filter and its own slice of the
+    /// computed report, while the device-epoch-wide `C`/`QTrigger` filters
+    /// are consumed once per epoch, atomically alongside every querier's
+    /// `Nc` filter, so a single report never partially deducts budget.
     pub fn compute_report(
         &mut self,
         request: &Q,
     ) -> Result<HashMap<Q::Uri, PdsReport<Q>>, ERR> {
         debug!("Computing report for request {:?}", request);
 
-        // Check if this is a multi-beneficiary query, which we don't support
-        // yet
-        if request.report_uris().querier_uris.len() > 1 {
-            todo!("Implement multi-beneficiary queries");
-        }
+        let uris = request.report_uris();
 
         // Collect events from event storage by epoch. If an epoch has no
         // relevant events, don't add it to the mapping.
@@ -266,63 +664,66 @@ where
             let epoch_relevant_events =
                 relevant_events_per_epoch.get(&epoch_id);
 
-            // Step 2. Compute individual loss for current epoch.
-            let individual_privacy_loss = self.compute_epoch_loss(
-                request,
-                epoch_relevant_events,
-                unfiltered_result
-                    .uri_report_map
-                    .get(&request.report_uris().querier_uris[0])
-                    .unwrap(),
-                num_epochs,
-            );
-
             // Step 3. Get relevant events for the current epoch `epoch_id` per
             // source.
             let epoch_source_relevant_events =
                 relevant_events_per_epoch_source.get(&epoch_id);
 
-            // Step 4. Compute device-epoch-source losses.
-            let source_losses = self.compute_epoch_source_losses(
-                request,
-                epoch_source_relevant_events,
-                unfiltered_result
+            // Step 2 & 4. For every querier, compute its own individual
+            // privacy loss (Step 2) against its own slice of
+            // `unfiltered_result`, plus its own device-epoch-source losses
+            // (Step 4). Per-source losses take the maximum across queriers,
+            // since that filter is shared and consumed once per epoch no
+            // matter how many queriers the report serves.
+            let mut losses_by_querier: HashMap<U, PureDPBudget> =
+                HashMap::new();
+            let mut source_losses: HashMap<U, PureDPBudget> = HashMap::new();
+            for querier_uri in &uris.querier_uris {
+                let querier_unfiltered_report = unfiltered_result
                     .uri_report_map
-                    .get(&request.report_uris().querier_uris[0])
-                    .unwrap(),
-                num_epochs,
-            );
-
-            // Step 5. Try to consume budget from current epoch, drop events if
-            // OOB. Two phase commit.
+                    .get(querier_uri)
+                    .unwrap();
+
+                let individual_privacy_loss = self.compute_epoch_loss(
+                    request,
+                    epoch_relevant_events,
+                    querier_unfiltered_report,
+                    num_epochs,
+                );
+                losses_by_querier
+                    .insert(querier_uri.clone(), individual_privacy_loss);
+
+                let querier_source_losses = self.compute_epoch_source_losses(
+                    request,
+                    epoch_source_relevant_events,
+                    querier_unfiltered_report,
+                    num_epochs,
+                );
+                for (source, loss) in querier_source_losses {
+                    source_losses
+                        .entry(source)
+                        .and_modify(|existing| {
+                            if loss > *existing {
+                                *existing = loss;
+                            }
+                        })
+                        .or_insert(loss);
+                }
+            }
 
-            // Phase 1: dry run.
-            let check_status = self.deduct_budget(
+            // Step 5. Try to consume budget from current epoch, drop events
+            // if OOB. Atomic, all-or-nothing across every filter touched by
+            // this epoch, including every querier's `Nc` filter.
+            let filters_to_consume = Self::filters_to_consume(
                 &epoch_id,
-                &individual_privacy_loss,
+                &losses_by_querier,
                 &source_losses,
-                request.report_uris(),
-                true, // dry run
-            )?;
-
-            match check_status {
-                PdsFilterStatus::Continue => {
-                    // Phase 2: Consume the budget
-                    let consume_status = self.deduct_budget(
-                        &epoch_id,
-                        &individual_privacy_loss,
-                        &source_losses,
-                        request.report_uris(),
-                        false, // actually consume
-                    )?;
-
-                    if consume_status != PdsFilterStatus::Continue {
-                        return Err(anyhow::anyhow!(
-                            "ERR: Phase 2 failed unexpectedly wtih status {:?} after Phase 1 succeeded", 
-                            consume_status,
-                        ).into());
-                    }
-                }
+                &uris,
+            );
+
+            match self.deduct_budget(&filters_to_consume)? {
+                PdsFilterStatus::Continue => {}
+
                 PdsFilterStatus::OutOfBudget(mut filters) => {
                     // Not enough budget, drop events without any filter
                     // consumption
@@ -334,23 +735,39 @@ where
             }
         }
 
-        // Now that we've dropped OOB epochs, we can compute the final report.
+        // Now that we've dropped OOB epochs, we can compute the final
+        // report. Build one report per querier URI, each only listing the
+        // `oob_filters` relevant to it (its own `Nc` filter plus the shared
+        // `C`/`QTrigger`/`QSource` ones), so an epoch dropped because one
+        // querier's `Nc` filter ran out doesn't falsely blame another
+        // querier's filter in its report.
         let filtered_result =
             request.compute_report(&relevant_events_per_epoch);
-        let main_report = PdsReport {
-            filtered_report: filtered_result
+        let mut reports = HashMap::new();
+        for querier_uri in &uris.querier_uris {
+            let filtered_report = filtered_result
                 .uri_report_map
-                .get(&request.report_uris().querier_uris[0])
+                .get(querier_uri)
                 .unwrap()
-                .clone(),
-            unfiltered_report: unfiltered_result
+                .clone();
+            let unfiltered_report = unfiltered_result
                 .uri_report_map
-                .get(&request.report_uris().querier_uris[0])
+                .get(querier_uri)
                 .unwrap()
-                .clone(),
-            oob_filters,
-        };
-
+                .clone();
+
+            reports.insert(
+                querier_uri.clone(),
+                PdsReport {
+                    filtered_report,
+                    unfiltered_report,
+                    oob_filters: Self::filters_relevant_to_querier(
+                        &oob_filters,
+                        querier_uri,
+                    ),
+                },
+            );
+        }
         // Handle optimization queries when at least two intermediary URIs are
         // in the request.
         if self.is_optimization_query(filtered_result.uri_report_map) {
@@ -388,7 +805,7 @@ where
                                 .get(&intermediary_uri)
                                 .unwrap()
                                 .clone(),
-                            oob_filters: main_report.oob_filters.clone(),
+                            oob_filters: oob_filters.clone(),
                         };
 
                         // Add this code to deduct budget for the intermediary
@@ -412,10 +829,7 @@ where
 
         // For regular requests or optimization queries without intermediary
         // reports
-        Ok(HashMap::from([(
-            request.report_uris().querier_uris[0].clone(),
-            main_report,
-        )]))
+        Ok(reports)
     }
 
     /// [Experimental] Accounts for passive privacy loss. Can fail if the
@@ -428,36 +842,27 @@ where
         &mut self,
         request: PassivePrivacyLossRequest<EI, U, PureDPBudget>,
     ) -> Result<PdsFilterStatus<FilterId<EI, U>>, ERR> {
+        // The same `privacy_budget` applies to every querier's `Nc` filter,
+        // same as the shared `C`/`QTrigger` filters.
+        let losses_by_querier: HashMap<U, PureDPBudget> = request
+            .uris
+            .querier_uris
+            .iter()
+            .map(|uri| (uri.clone(), request.privacy_budget.clone()))
+            .collect();
         let source_losses = HashMap::new(); // Dummy.
 
-        // For each epoch, try to consume the privacy budget.
+        // For each epoch, atomically try to consume the privacy budget.
         for epoch_id in request.epoch_ids {
-            // Phase 1: dry run.
-            let check_status = self.deduct_budget(
+            let filters_to_consume = Self::filters_to_consume(
                 &epoch_id,
-                &request.privacy_budget,
+                &losses_by_querier,
                 &source_losses,
-                request.uris.clone(),
-                true, // dry run
-            )?;
-            if check_status != PdsFilterStatus::Continue {
-                return Ok(check_status);
-            }
-
-            // Phase 2: Consume the budget
-            let consume_status = self.deduct_budget(
-                &epoch_id,
-                &request.privacy_budget,
-                &source_losses,
-                request.uris.clone(),
-                false, // actually consume
-            )?;
-
-            if consume_status != PdsFilterStatus::Continue {
-                return Err(anyhow::anyhow!(
-                    "ERR: Phase 2 failed unexpectedly wtih status {:?} after Phase 1 succeeded", 
-                    consume_status,
-                ).into());
+                &request.uris,
+            );
+            let status = self.deduct_budget(&filters_to_consume)?;
+            if status != PdsFilterStatus::Continue {
+                return Ok(status);
             }
 
             // TODO(https://github.com/columbia/pdslib/issues/16): semantics are still unclear, for now we ignore the request if
@@ -466,22 +871,65 @@ where
         Ok(PdsFilterStatus::Continue)
     }
 
-    fn initialize_filter_if_necessary(
-        &mut self,
-        filter_id: FilterId<EI, U>,
-    ) -> Result<(), ERR> {
-        let filter_initialized =
-            self.filter_storage.is_initialized(&filter_id)?;
-
-        if !filter_initialized {
-            let create_filter_result =
-                self.filter_storage.new_filter(filter_id);
+    /// Builds the map of filters a single epoch's report touches: every
+    /// querier's own `Nc` filter (debited with its own loss), the shared
+    /// `C`/`QTrigger` filters (debited with the maximum loss across
+    /// queriers, a sound upper bound on what any single querier needs),
+    /// and the per-source `QSource` filters.
+    fn filters_to_consume<'a>(
+        epoch_id: &EI,
+        losses_by_querier: &'a HashMap<U, PureDPBudget>,
+        source_losses: &'a HashMap<U, PureDPBudget>,
+        uris: &ReportRequestUris<U>,
+    ) -> HashMap<FilterId<EI, U>, &'a PureDPBudget> {
+        let mut filters_to_consume = HashMap::new();
 
-            if create_filter_result.is_err() {
-                return Ok(());
+        for query_uri in &uris.querier_uris {
+            if let Some(loss) = losses_by_querier.get(query_uri) {
+                filters_to_consume.insert(
+                    FilterId::Nc(epoch_id.clone(), query_uri.clone()),
+                    loss,
+                );
             }
         }
-        Ok(())
+
+        if let Some(shared_loss) = losses_by_querier
+            .values()
+            .max_by(|a, b| a.partial_cmp(b).unwrap())
+        {
+            filters_to_consume.insert(
+                FilterId::QTrigger(epoch_id.clone(), uris.trigger_uri.clone()),
+                shared_loss,
+            );
+            filters_to_consume
+                .insert(FilterId::C(epoch_id.clone()), shared_loss);
+        }
+
+        for (source, loss) in source_losses {
+            let fid = FilterId::QSource(epoch_id.clone(), source.clone());
+            filters_to_consume.insert(fid, loss);
+        }
+
+        filters_to_consume
+    }
+
+    /// Filters `oob_filters` down to the ones relevant to `querier_uri`: its
+    /// own `Nc` filter, plus every shared (`C`, `QTrigger`, `QSource`)
+    /// filter, which apply regardless of querier.
+    fn filters_relevant_to_querier(
+        oob_filters: &[FilterId<EI, U>],
+        querier_uri: &U,
+    ) -> Vec<FilterId<EI, U>> {
+        oob_filters
+            .iter()
+            .filter(|filter_id| match filter_id {
+                FilterId::Nc(_, uri) => uri == querier_uri,
+                FilterId::C(_)
+                | FilterId::QTrigger(_, _)
+                | FilterId::QSource(_, _) => true,
+            })
+            .cloned()
+            .collect()
     }
 
     /// Compute the privacy loss at the device-epoch-source level.
@@ -550,53 +998,35 @@ where
         per_source_losses
     }
 
-    /// Deduct the privacy loss from the various filters.
+    /// Deduct the privacy loss from the various filters, atomically: either
+    /// every filter in `filters_to_consume` gets debited, or (if any of
+    /// them is out of budget) none of them do. Uses a `FilterTransaction`
+    /// instead of the old hand-rolled two-phase commit (a `can_consume` dry
+    /// run over every filter, then a `try_consume` pass that was expected,
+    /// but not guaranteed, to agree with it).
     fn deduct_budget(
         &mut self,
-        epoch_id: &EI,
-        loss: &FS::Budget,
-        source_losses: &HashMap<U, FS::Budget>,
-        uris: ReportRequestUris<U>,
-        dry_run: bool,
+        filters_to_consume: &HashMap<FilterId<EI, U>, &PureDPBudget>,
     ) -> Result<PdsFilterStatus<FilterId<EI, U>>, ERR> {
-        // Build the filter IDs for NC, C and QTrigger
-        let mut device_epoch_filter_ids = Vec::new();
-        for query_uri in uris.querier_uris {
-            device_epoch_filter_ids
-                .push(FilterId::Nc(epoch_id.clone(), query_uri));
-        }
-        device_epoch_filter_ids
-            .push(FilterId::QTrigger(epoch_id.clone(), uris.trigger_uri));
-        device_epoch_filter_ids.push(FilterId::C(epoch_id.clone()));
-
-        // NC, C and QTrigger all have the same device-epoch level loss
-        let mut filters_to_consume = HashMap::new();
-        for filter_id in device_epoch_filter_ids {
-            filters_to_consume.insert(filter_id, loss);
-        }
-
-        // Add the QSource filters with their own device-epoch-source level loss
-        for (source, loss) in source_losses {
-            let fid = FilterId::QSource(epoch_id.clone(), source.clone());
-            filters_to_consume.insert(fid, loss);
-        }
-
-        // Try to consume the privacy loss from the filters
+        let mut txn = self.filter_storage.begin_transaction();
         let mut oob_filters = vec![];
+
         for (fid, loss) in filters_to_consume {
-            self.initialize_filter_if_necessary(fid.clone())?;
             let filter_status =
-                self.filter_storage.maybe_consume(&fid, loss, dry_run)?;
+                self.filter_storage.try_consume_in(&mut txn, fid, loss)?;
             if filter_status == FilterStatus::OutOfBudget {
-                oob_filters.push(fid);
+                oob_filters.push(fid.clone());
             }
         }
 
-        // If any filter was out of budget, the whole operation is marked as out
-        // of budget.
+        // If any filter was out of budget, roll back and mark the whole
+        // operation as out of budget.
         if !oob_filters.is_empty() {
+            self.filter_storage.rollback(txn);
             return Ok(PdsFilterStatus::OutOfBudget(oob_filters));
         }
+
+        self.filter_storage.commit(txn)?;
         Ok(PdsFilterStatus::Continue)
     }
 
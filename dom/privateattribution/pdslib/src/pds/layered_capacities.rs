@@ -0,0 +1,188 @@
+use std::{
+    fmt::Debug,
+    ops::RangeInclusive,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    budget::traits::{Budget, FilterCapacities},
+    pds::quotas::{CapacityError, FilterId, StaticCapacities},
+};
+
+/// Which kind of filter a [`CapacityOverride`] applies to, mirroring the
+/// variants of [`FilterId`] without the epoch/URI payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FilterKind {
+    Nc,
+    C,
+    QTrigger,
+    QSource,
+}
+
+impl<E, U> From<&FilterId<E, U>> for FilterKind {
+    fn from(filter_id: &FilterId<E, U>) -> Self {
+        match filter_id {
+            FilterId::Nc(..) => FilterKind::Nc,
+            FilterId::C(..) => FilterKind::C,
+            FilterId::QTrigger(..) => FilterKind::QTrigger,
+            FilterId::QSource(..) => FilterKind::QSource,
+        }
+    }
+}
+
+/// One capacity override rule: applies `capacity` to filters of `kind`,
+/// within `epoch_range`, and (optionally) matching `uri`. A `None` URI acts
+/// as a wildcard matching any URI for that filter kind.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapacityOverride<E, U, B> {
+    pub epoch_range: RangeInclusive<E>,
+    pub uri: Option<U>,
+    pub kind: FilterKind,
+    pub capacity: B,
+}
+
+impl<E: PartialOrd, U: PartialEq, B> CapacityOverride<E, U, B> {
+    /// How specific this rule is, relative to others matching the same
+    /// filter id: a single-epoch range and an exact URI match each count
+    /// towards specificity (multi-epoch ranges all score the same, however
+    /// wide), so the most targeted override wins.
+    fn specificity(&self) -> u8 {
+        let mut score = 0;
+        if self.epoch_range.start() == self.epoch_range.end() {
+            score += 1;
+        }
+        if self.uri.is_some() {
+            score += 1;
+        }
+        score
+    }
+
+    fn matches(&self, epoch_id: &E, uri: Option<&U>, kind: FilterKind) -> bool {
+        self.kind == kind
+            && self.epoch_range.contains(epoch_id)
+            && match (&self.uri, uri) {
+                (None, _) => true,
+                (Some(rule_uri), Some(event_uri)) => rule_uri == event_uri,
+                (Some(_), None) => false,
+            }
+    }
+}
+
+/// [`FilterCapacities`] implementation that layers per-URI and per-epoch
+/// overrides on top of a `StaticCapacities` default, so deployments can
+/// grant tighter or looser quotas to specific source/trigger URIs, or ramp
+/// capacity up over a range of epochs, without forking the whole capacity
+/// model.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayeredCapacities<E, U, B> {
+    base: StaticCapacities<FilterId<E, U>, B>,
+    overrides: Vec<CapacityOverride<E, U, B>>,
+}
+
+impl<E, U, B> LayeredCapacities<E, U, B> {
+    pub fn new(base: StaticCapacities<FilterId<E, U>, B>) -> Self {
+        Self {
+            base,
+            overrides: Vec::new(),
+        }
+    }
+
+    /// Registers an override rule. At lookup time the most specific
+    /// matching rule wins; among equally specific matches, the one
+    /// registered last wins.
+    pub fn with_override(mut self, rule: CapacityOverride<E, U, B>) -> Self {
+        self.overrides.push(rule);
+        self
+    }
+}
+
+impl<E, U, B> FilterCapacities for LayeredCapacities<E, U, B>
+where
+    E: Debug + PartialOrd,
+    U: Debug + PartialEq,
+    B: Budget,
+{
+    type FilterId = FilterId<E, U>;
+    type Budget = B;
+    type Error = CapacityError<FilterId<E, U>>;
+
+    fn capacity(
+        &self,
+        filter_id: &Self::FilterId,
+    ) -> Result<Self::Budget, Self::Error> {
+        let kind = FilterKind::from(filter_id);
+        let (epoch_id, uri) = match filter_id {
+            FilterId::Nc(epoch_id, uri) => (epoch_id, Some(uri)),
+            FilterId::C(epoch_id) => (epoch_id, None),
+            FilterId::QTrigger(epoch_id, uri) => (epoch_id, Some(uri)),
+            FilterId::QSource(epoch_id, uri) => (epoch_id, Some(uri)),
+        };
+
+        let best_match = self
+            .overrides
+            .iter()
+            .filter(|rule| rule.matches(epoch_id, uri, kind))
+            .max_by_key(|rule| rule.specificity());
+
+        match best_match {
+            Some(rule) => Ok(rule.capacity.clone()),
+            None => self.base.capacity(filter_id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_falls_back_to_static_default() -> Result<(), CapacityError<FilterId<u64, String>>>
+    {
+        let capacities: LayeredCapacities<u64, String, f64> =
+            LayeredCapacities::new(StaticCapacities::mock());
+
+        let fid = FilterId::QSource(1, "blog.com".to_string());
+        assert_eq!(capacities.capacity(&fid)?, 4.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_uri_specific_override_wins_over_wildcard(
+    ) -> Result<(), CapacityError<FilterId<u64, String>>> {
+        let capacities = LayeredCapacities::new(StaticCapacities::mock())
+            .with_override(CapacityOverride {
+                epoch_range: 0..=u64::MAX,
+                uri: None,
+                kind: FilterKind::QSource,
+                capacity: 10.0,
+            })
+            .with_override(CapacityOverride {
+                epoch_range: 0..=u64::MAX,
+                uri: Some("blog.com".to_string()),
+                kind: FilterKind::QSource,
+                capacity: 1.0,
+            });
+
+        let blog = FilterId::QSource(1, "blog.com".to_string());
+        let other = FilterId::QSource(1, "other.com".to_string());
+        assert_eq!(capacities.capacity(&blog)?, 1.0);
+        assert_eq!(capacities.capacity(&other)?, 10.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_epoch_range_override() -> Result<(), CapacityError<FilterId<u64, String>>> {
+        let capacities = LayeredCapacities::new(StaticCapacities::mock())
+            .with_override(CapacityOverride {
+                epoch_range: 5..=5,
+                uri: None,
+                kind: FilterKind::C,
+                capacity: 100.0,
+            });
+
+        assert_eq!(capacities.capacity(&FilterId::C(5))?, 100.0);
+        assert_eq!(capacities.capacity(&FilterId::C(6))?, 20.0);
+        Ok(())
+    }
+}
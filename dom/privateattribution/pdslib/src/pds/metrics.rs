@@ -0,0 +1,325 @@
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Display},
+};
+
+use crate::budget::traits::{EpochScopedFilterId, FilterKind};
+
+/// Default histogram bucket upper bounds for [`MetricsHistogram`]. Mirrors
+/// `DEFAULT_BUCKETS` in the Firefox-side `PdslibMetrics` (same Prometheus
+/// client-library defaults), since every value tracked through this
+/// histogram (epsilon spend, individual sensitivity) is a `PureDPBudget`-ish
+/// small positive number spread across a few orders of magnitude there too.
+pub const DEFAULT_METRICS_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// A simple cumulative histogram of budget-shaped values (per-request
+/// epsilon consumption, individual sensitivity), as aggregated by
+/// [`AggregatingMetricsSink`]. Unlike the Firefox-side `Histogram` (which
+/// must be safely updatable from `&self` across threads), this one is only
+/// ever touched through `&mut self` metrics callbacks, so plain counters
+/// suffice -- no atomics needed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricsHistogram {
+    /// Sorted bucket upper bounds, not including the implicit `+Inf`
+    /// bucket.
+    bounds: Vec<f64>,
+    /// Cumulative per-bucket counts, same length and order as `bounds`.
+    bucket_counts: Vec<u64>,
+    count: u64,
+    sum: f64,
+}
+
+impl Default for MetricsHistogram {
+    fn default() -> Self {
+        Self::new(DEFAULT_METRICS_BUCKETS.to_vec())
+    }
+}
+
+impl MetricsHistogram {
+    pub fn new(mut bounds: Vec<f64>) -> Self {
+        bounds.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let bucket_counts = vec![0; bounds.len()];
+        Self {
+            bounds,
+            bucket_counts,
+            count: 0,
+            sum: 0.0,
+        }
+    }
+
+    /// Records `value`: increments every bucket whose bound is `>= value`
+    /// (cumulative semantics), plus the total count and sum.
+    pub fn observe(&mut self, value: f64) {
+        for (bound, bucket_count) in
+            self.bounds.iter().zip(self.bucket_counts.iter_mut())
+        {
+            if value <= *bound {
+                *bucket_count += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += value;
+    }
+
+    /// The bucket upper bounds this histogram was created with.
+    pub fn bounds(&self) -> &[f64] {
+        &self.bounds
+    }
+
+    /// Cumulative counts per bucket, same order as [`Self::bounds`].
+    pub fn bucket_counts(&self) -> &[u64] {
+        &self.bucket_counts
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn sum(&self) -> f64 {
+        self.sum
+    }
+}
+
+/// Observer hook for [`PrivateDataServiceCore`](super::core::PrivateDataServiceCore),
+/// fired on budget-relevant events so operators can quantify budget
+/// pressure (e.g. to tune [`StaticCapacities`](super::quotas::StaticCapacities))
+/// without instrumenting call sites by hand. Modeled after Garage's admin
+/// `metrics` module: a sink trait with one callback per event, all
+/// defaulted to no-ops so a sink only needs to override the events it
+/// cares about.
+pub trait PdsMetricsSink<EpochId, FilterId, Uri> {
+    /// Fired once per filter debited by `deduct_budget`, after the debit is
+    /// committed to storage.
+    fn on_budget_consumed(
+        &mut self,
+        _filter_id: &FilterId,
+        _amount: f64,
+        _remaining: f64,
+    ) {
+    }
+
+    /// Fired when `compute_report` drops an epoch for being out of budget.
+    fn on_epoch_dropped(
+        &mut self,
+        _epoch_id: EpochId,
+        _oob_filters: &[FilterId],
+    ) {
+    }
+
+    /// Fired once per `compute_report` call, after the report is built.
+    fn on_report_computed(&mut self, _querier_uri: &Uri, _num_epochs: usize) {}
+
+    /// Fired once per `deduct_budget` call that succeeds (i.e. once per
+    /// epoch touched by `compute_report`/`account_for_passive_privacy_loss`),
+    /// with the total epsilon consumed across every filter debited in that
+    /// call. Lets operators track the distribution of per-request privacy
+    /// spend, not just the running total from `on_budget_consumed`.
+    fn on_epsilon_consumed(&mut self, _total_epsilon: f64) {}
+
+    /// Fired once per individual privacy loss (the epoch's individual
+    /// sensitivity divided by the noise scale) computed by
+    /// `compute_epoch_loss`/`compute_epoch_source_losses` while building a
+    /// report, before the atomic dry-run check. Lets operators see the
+    /// distribution of per-epoch losses a querier/source requests, not
+    /// just what eventually gets debited (a dropped epoch consumes no
+    /// budget but still computed a loss here).
+    fn on_individual_sensitivity(&mut self, _sensitivity: f64) {}
+}
+
+/// Default sink: every callback is a no-op. Used unless
+/// `PrivateDataServiceCore` is configured via `with_metrics_sink`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoopMetricsSink;
+
+impl<EpochId, FilterId, Uri> PdsMetricsSink<EpochId, FilterId, Uri>
+    for NoopMetricsSink
+{
+}
+
+/// In-memory sink that aggregates budget-consumption and drop events into
+/// running totals, suitable for periodic snapshotting onto an
+/// operator-facing dashboard via [`Self::snapshot`].
+///
+/// Keys by `Display`/`Debug` string representations rather than the typed
+/// `FilterId`/`EpochId` (same pattern as
+/// [`BudgetSummary::consumed_by_filter_id`](crate::budget::traits::BudgetSummary::consumed_by_filter_id)),
+/// so it doesn't need `Eq + Hash` bounds on them.
+#[derive(Debug, Default, Clone)]
+pub struct AggregatingMetricsSink {
+    total_consumed: f64,
+    consumed_by_filter_id: HashMap<String, f64>,
+    /// Remaining budget reported alongside the most recent debit of each
+    /// filter, i.e. a live gauge of how close each filter is to exhaustion.
+    remaining_by_filter_id: HashMap<String, f64>,
+    loss_by_epoch: HashMap<String, f64>,
+    /// Successful-deduction amount, summed per filter kind (`Nc`/`C`/
+    /// `QTrigger`/`QSource`).
+    consumed_by_kind: HashMap<&'static str, f64>,
+    oob_epoch_count: usize,
+    oob_count_by_filter_id: HashMap<String, usize>,
+    /// Out-of-budget rejection count, broken down per filter kind.
+    oob_count_by_kind: HashMap<&'static str, usize>,
+    reports_computed: usize,
+    requested_epsilon: MetricsHistogram,
+    /// Distribution of individual privacy losses returned by
+    /// `compute_epoch_loss`/`compute_epoch_source_losses`.
+    individual_sensitivity: MetricsHistogram,
+}
+
+impl AggregatingMetricsSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes a read-only snapshot of the metrics aggregated so far.
+    pub fn snapshot(&self) -> PdsMetricsSnapshot {
+        PdsMetricsSnapshot {
+            total_consumed: self.total_consumed,
+            consumed_by_filter_id: self.consumed_by_filter_id.clone(),
+            remaining_by_filter_id: self.remaining_by_filter_id.clone(),
+            loss_by_epoch: self.loss_by_epoch.clone(),
+            consumed_by_kind: self.consumed_by_kind.clone(),
+            oob_epoch_count: self.oob_epoch_count,
+            oob_count_by_filter_id: self.oob_count_by_filter_id.clone(),
+            oob_count_by_kind: self.oob_count_by_kind.clone(),
+            reports_computed: self.reports_computed,
+            requested_epsilon: self.requested_epsilon.clone(),
+            individual_sensitivity: self.individual_sensitivity.clone(),
+        }
+    }
+}
+
+impl<EpochId, FilterId, Uri> PdsMetricsSink<EpochId, FilterId, Uri>
+    for AggregatingMetricsSink
+where
+    FilterId: Display + EpochScopedFilterId + FilterKind,
+    FilterId::Epoch: Debug,
+{
+    fn on_budget_consumed(
+        &mut self,
+        filter_id: &FilterId,
+        amount: f64,
+        remaining: f64,
+    ) {
+        self.total_consumed += amount;
+        *self
+            .consumed_by_filter_id
+            .entry(filter_id.to_string())
+            .or_insert(0.0) += amount;
+        self.remaining_by_filter_id
+            .insert(filter_id.to_string(), remaining);
+        *self
+            .loss_by_epoch
+            .entry(format!("{:?}", filter_id.epoch()))
+            .or_insert(0.0) += amount;
+        *self
+            .consumed_by_kind
+            .entry(filter_id.kind())
+            .or_insert(0.0) += amount;
+    }
+
+    fn on_epoch_dropped(&mut self, _epoch_id: EpochId, oob_filters: &[FilterId]) {
+        self.oob_epoch_count += 1;
+        for filter_id in oob_filters {
+            *self
+                .oob_count_by_filter_id
+                .entry(filter_id.to_string())
+                .or_insert(0) += 1;
+            *self.oob_count_by_kind.entry(filter_id.kind()).or_insert(0) += 1;
+        }
+    }
+
+    fn on_report_computed(&mut self, _querier_uri: &Uri, _num_epochs: usize) {
+        self.reports_computed += 1;
+    }
+
+    fn on_epsilon_consumed(&mut self, total_epsilon: f64) {
+        self.requested_epsilon.observe(total_epsilon);
+    }
+
+    fn on_individual_sensitivity(&mut self, sensitivity: f64) {
+        self.individual_sensitivity.observe(sensitivity);
+    }
+}
+
+/// Snapshot of an [`AggregatingMetricsSink`]'s running totals, as returned by
+/// [`AggregatingMetricsSink::snapshot`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PdsMetricsSnapshot {
+    pub total_consumed: f64,
+    pub consumed_by_filter_id: HashMap<String, f64>,
+    pub remaining_by_filter_id: HashMap<String, f64>,
+    pub loss_by_epoch: HashMap<String, f64>,
+    pub consumed_by_kind: HashMap<&'static str, f64>,
+    pub oob_epoch_count: usize,
+    pub oob_count_by_filter_id: HashMap<String, usize>,
+    pub oob_count_by_kind: HashMap<&'static str, usize>,
+    pub reports_computed: usize,
+    pub requested_epsilon: MetricsHistogram,
+    pub individual_sensitivity: MetricsHistogram,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pds::quotas::FilterId;
+
+    #[test]
+    fn test_consumed_and_oob_broken_down_by_kind() {
+        let mut sink = AggregatingMetricsSink::new();
+
+        sink.on_budget_consumed(&FilterId::Nc(1, "a.com".to_string()), 0.5, 0.5);
+        sink.on_budget_consumed(&FilterId::C(1), 0.2, 19.8);
+        sink.on_budget_consumed(&FilterId::Nc(2, "b.com".to_string()), 0.3, 0.7);
+
+        sink.on_epoch_dropped(
+            3,
+            &[FilterId::QTrigger(3, "shoes.com".to_string())],
+        );
+
+        let snapshot = sink.snapshot();
+        assert_eq!(snapshot.consumed_by_kind.get("Nc"), Some(&0.8));
+        assert_eq!(snapshot.consumed_by_kind.get("C"), Some(&0.2));
+        assert_eq!(snapshot.oob_count_by_kind.get("QTrigger"), Some(&1));
+        assert_eq!(snapshot.oob_count_by_kind.get("Nc"), None);
+        assert_eq!(
+            snapshot
+                .remaining_by_filter_id
+                .get(&FilterId::C(1).to_string()),
+            Some(&19.8)
+        );
+    }
+
+    #[test]
+    fn test_individual_sensitivity_histogram_observes_every_value() {
+        let mut sink = AggregatingMetricsSink::new();
+
+        sink.on_individual_sensitivity(0.05);
+        sink.on_individual_sensitivity(0.5);
+
+        let histogram = sink.snapshot().individual_sensitivity;
+        assert_eq!(histogram.count(), 2);
+        assert_eq!(histogram.sum(), 0.55);
+    }
+
+    #[test]
+    fn test_epsilon_histogram_observes_per_request_totals() {
+        let mut sink = AggregatingMetricsSink::new();
+
+        sink.on_epsilon_consumed(0.05);
+        sink.on_epsilon_consumed(1.0);
+        sink.on_epsilon_consumed(3.0);
+
+        let histogram = sink.snapshot().requested_epsilon;
+        assert_eq!(histogram.count(), 3);
+        assert_eq!(histogram.sum(), 4.05);
+
+        // 0.1 is one of the default bucket bounds; only the 0.05 sample
+        // falls at or below it.
+        let bucket_index =
+            histogram.bounds().iter().position(|&b| b == 0.1).unwrap();
+        assert_eq!(histogram.bucket_counts()[bucket_index], 1);
+    }
+}
@@ -1,14 +1,23 @@
 use std::{collections::HashMap, fmt::Debug};
 
 use log::debug;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use super::{
-    core::PrivateDataServiceCore,
+    core::{BatchPolicy, PrivateDataServiceCore},
     quotas::{FilterId, PdsFilterStatus},
 };
 use crate::{
-    budget::{pure_dp_filter::PureDPBudget, traits::FilterStorage},
-    events::{relevant_events::RelevantEvents, traits::EventStorage},
+    budget::{
+        hashmap_filter_storage::HashMapFilterStorage,
+        pure_dp_filter::{PureDPBudget, PureDPBudgetFilter},
+        snapshot::{self, SnapshotError},
+        traits::{EpochScopedFilterId, FilterCapacities, FilterStorage},
+    },
+    events::{
+        hashmap_event_storage::HashMapEventStorage, relevant_events::RelevantEvents,
+        traits::{Event, EventStorage, RelevantEventSelector},
+    },
     queries::traits::{EpochReportRequest, PassivePrivacyLossRequest},
 };
 
@@ -33,6 +42,13 @@ pub struct PrivateDataService<
     pub event_storage: ES,
 }
 
+/// Identifies one request within a [`PrivateDataService::compute_reports_batch`]
+/// call, by that request's position in the input slice. `Q` has no identity
+/// of its own (two requests can be structurally identical, e.g. the same
+/// querier re-running the same attribution window), so the caller's
+/// submission order is the only stable handle available.
+pub type RequestId = usize;
+
 /// Report returned by Pds, potentially augmented with debugging information
 #[derive(Default, Debug)]
 pub struct PdsReport<Q: EpochReportRequest> {
@@ -55,6 +71,7 @@ where
         Budget = PureDPBudget,
         FilterId = FilterId<Q::EpochId, Q::Uri>,
     >,
+    FS::Filter: Clone,
     ES: EventStorage<Event = Q::Event>,
     ERR: From<FS::Error> + From<ES::Error> + From<anyhow::Error>,
 {
@@ -87,6 +104,103 @@ where
         self.core.compute_report(request, relevant_events)
     }
 
+    /// Computes reports for a batch of related requests in one call,
+    /// fetching each request's relevant events and then delegating to
+    /// [`PrivateDataServiceCore::compute_report_batch`] with
+    /// [`BatchPolicy::GreedyOrdered`]: requests are applied in submission
+    /// order, so a later request sees budget already consumed by an earlier
+    /// one in the same batch, exactly as if `compute_report` had been called
+    /// on each in a loop. Returns one result per request, in the same order
+    /// as `requests`.
+    ///
+    /// Takes `requests` by value rather than `&[Q]`, since `Q` (e.g.
+    /// `PpaHistogramRequest`, whose `relevant_event_selector` holds a
+    /// `Box<dyn Fn>`) need not implement `Clone`.
+    pub fn compute_reports(
+        &mut self,
+        requests: Vec<Q>,
+    ) -> Result<Vec<HashMap<Q::Uri, PdsReport<Q>>>, ERR> {
+        let mut batch = Vec::with_capacity(requests.len());
+        for request in requests {
+            let relevant_event_selector = request.relevant_event_selector();
+            let relevant_events = RelevantEvents::from_event_storage(
+                &mut self.event_storage,
+                &request.epoch_ids(),
+                relevant_event_selector,
+            )?;
+            batch.push((request, relevant_events));
+        }
+
+        self.core.compute_report_batch(&batch, BatchPolicy::GreedyOrdered)
+    }
+
+    /// Computes reports for a batch of requests that may come from different
+    /// queriers and cover overlapping attribution windows, keyed by each
+    /// request's [`RequestId`] (its position in `requests`) rather than
+    /// returned as a plain `Vec` like [`Self::compute_reports`].
+    ///
+    /// Unlike `compute_reports`, each request's relevant-event read doesn't
+    /// hit `event_storage` on its own: every epoch referenced by any request
+    /// in the batch is fetched from storage at most once into a shared
+    /// cache, and each request then filters its own copy of the relevant
+    /// epochs out of that cache via its `RelevantEventSelector`. This avoids
+    /// re-fetching the same epoch's events once per request when several
+    /// conversions on the same device query overlapping windows.
+    ///
+    /// Each request still gets its own independent atomic budget dry-run/
+    /// consume via [`PrivateDataServiceCore::compute_report`]: one request
+    /// running out of budget is reflected in its own `PdsReport::oob_filters`
+    /// and never aborts or skips the rest of the batch. Takes `requests` by
+    /// shared reference, unlike `compute_reports`, since nothing here needs
+    /// to own a request -- `compute_report` only ever borrows it.
+    pub fn compute_reports_batch(
+        &mut self,
+        requests: &[Q],
+    ) -> Result<HashMap<RequestId, HashMap<Q::Uri, PdsReport<Q>>>, ERR> {
+        let mut raw_events_by_epoch: HashMap<Q::EpochId, Vec<Q::Event>> =
+            HashMap::new();
+        for request in requests {
+            for epoch_id in request.epoch_ids() {
+                if let std::collections::hash_map::Entry::Vacant(entry) =
+                    raw_events_by_epoch.entry(epoch_id)
+                {
+                    let events: Vec<Q::Event> =
+                        self.event_storage.events_for_epoch(&epoch_id)?.collect();
+                    entry.insert(events);
+                }
+            }
+        }
+
+        let mut results = HashMap::with_capacity(requests.len());
+        for (request_id, request) in requests.iter().enumerate() {
+            let selector = request.relevant_event_selector();
+            let events_per_epoch = request
+                .epoch_ids()
+                .into_iter()
+                .map(|epoch_id| {
+                    let relevant_events = raw_events_by_epoch
+                        .get(&epoch_id)
+                        .map(|events| {
+                            events
+                                .iter()
+                                .filter(|event| selector.is_relevant_event(event))
+                                .cloned()
+                                .collect()
+                        })
+                        .unwrap_or_default();
+                    (epoch_id, relevant_events)
+                })
+                .collect();
+
+            let report = self
+                .core
+                .compute_report(request, RelevantEvents::from_mapping(events_per_epoch))?;
+            results.insert(request_id, report);
+        }
+
+        Ok(results)
+    }
+
     /// [Experimental] Accounts for passive privacy loss. Can fail if the
     /// implementation has an error, but failure must not leak the state of
     /// the filters.
@@ -109,26 +223,11 @@ where
                 &request.uris,
             );
 
-            // Phase 1: dry run.
-            let check_status = self.core.deduct_budget(
-                &filters_to_consume,
-                true, // dry run
-            )?;
-            if check_status != PdsFilterStatus::Continue {
-                return Ok(check_status);
-            }
-
-            // Phase 2: Consume the budget
-            let consume_status = self.core.deduct_budget(
-                &filters_to_consume,
-                false, // actually consume
-            )?;
-
-            if consume_status != PdsFilterStatus::Continue {
-                return Err(anyhow::anyhow!(
-                    "ERR: Phase 2 failed unexpectedly wtih status {:?} after Phase 1 succeeded", 
-                    consume_status,
-                ).into());
+            // Atomic, all-or-nothing across every filter touched by this
+            // epoch.
+            let status = self.core.deduct_budget(&filters_to_consume)?;
+            if status != PdsFilterStatus::Continue {
+                return Ok(status);
             }
 
             // TODO(https://github.com/columbia/pdslib/issues/16): semantics are still unclear, for now we ignore the request if
@@ -136,4 +235,118 @@ where
         }
         Ok(PdsFilterStatus::Continue)
     }
+
+    /// Bounds memory used by both storages: drops filter-storage entries and
+    /// event-storage epochs that fall outside the filter capacities'
+    /// retention window (see `FilterCapacities::retention`). An epoch past
+    /// that window can never be spent against again, so pruning it is
+    /// indistinguishable, to the privacy accounting, from that epoch never
+    /// having been created -- a later `compute_report` or
+    /// `account_for_passive_privacy_loss` referencing a pruned epoch just
+    /// sees no events and fresh, full-capacity filters, same as an epoch
+    /// that was never touched.
+    ///
+    /// A no-op returning `(0, 0)` if no retention policy is configured.
+    /// Returns `(filters_dropped, epochs_dropped)`.
+    pub fn prune(&mut self, now_epoch: Q::EpochId) -> Result<(usize, usize), ERR>
+    where
+        Q::EpochId: TryInto<i64>,
+        FS::FilterId: EpochScopedFilterId,
+        <FS::FilterId as EpochScopedFilterId>::Epoch: TryInto<i64>,
+    {
+        let Some(retention) = self.core.filter_storage.capacities().retention()
+        else {
+            return Ok((0, 0));
+        };
+        // An epoch id that doesn't fit in an `i64` is treated as arbitrarily
+        // far in the future (never stale): erring towards keeping state
+        // alive is always safe, unlike erring towards pruning an epoch
+        // that's still inside its retention window.
+        let now_epoch: i64 = now_epoch.try_into().unwrap_or(i64::MAX);
+        let oldest_live_epoch = now_epoch.saturating_sub(retention.window_epochs as i64);
+
+        let filters_dropped =
+            self.core.filter_storage.prune(oldest_live_epoch)?;
+        let epochs_dropped = self.event_storage.prune_before(|epoch_id| {
+            let epoch_id: i64 = (*epoch_id).try_into().unwrap_or(i64::MAX);
+            epoch_id < oldest_live_epoch
+        })?;
+
+        Ok((filters_dropped, epochs_dropped))
+    }
+}
+
+/// On-the-wire shape of a [`PrivateDataService::snapshot`]: filter storage
+/// and event storage bundled as sibling fields of one CBOR document, so they
+/// share a single [`snapshot::SNAPSHOT_SCHEMA_VERSION`] tag instead of being
+/// versioned (and restored) independently. A mismatch between the two --
+/// e.g. filters surviving a restart but events not, or vice versa -- would
+/// let a restored filter under-count privacy loss against events it no
+/// longer remembers, so they must be frozen and thawed together.
+#[derive(Serialize)]
+struct PrivateDataServiceSnapshotRef<'a, C, E>
+where
+    C: FilterCapacities,
+    E: Event,
+{
+    filter_storage: &'a HashMapFilterStorage<PureDPBudgetFilter, C>,
+    event_storage: &'a HashMapEventStorage<E>,
+}
+
+#[derive(Deserialize)]
+struct PrivateDataServiceSnapshot<C, E>
+where
+    C: FilterCapacities,
+    E: Event,
+{
+    filter_storage: HashMapFilterStorage<PureDPBudgetFilter, C>,
+    event_storage: HashMapEventStorage<E>,
+}
+
+impl<Q, C, ERR> PrivateDataService<Q, HashMapFilterStorage<PureDPBudgetFilter, C>, HashMapEventStorage<Q::Event>, ERR>
+where
+    Q: EpochReportRequest<Report: Clone>,
+    C: FilterCapacities<FilterId = FilterId<Q::EpochId, Q::Uri>, Budget = PureDPBudget>
+        + Serialize
+        + DeserializeOwned,
+    C::FilterId: Clone + Eq + std::hash::Hash + Debug + std::fmt::Display + Serialize + DeserializeOwned,
+    Q::Event: Serialize + DeserializeOwned,
+    <Q::Event as Event>::EpochId: Serialize + DeserializeOwned,
+    ERR: From<anyhow::Error>,
+{
+    /// Freezes filter storage and event storage into one versioned CBOR
+    /// blob, suitable for persisting across browser restarts. See
+    /// [`PrivateDataServiceSnapshotRef`] for why the two are bundled
+    /// together rather than snapshotted independently.
+    pub fn snapshot(&self) -> Result<Vec<u8>, SnapshotError> {
+        let snapshot = PrivateDataServiceSnapshotRef {
+            filter_storage: &self.core.filter_storage,
+            event_storage: &self.event_storage,
+        };
+        snapshot::to_cbor_snapshot(&snapshot)
+    }
+
+    /// Thaws a snapshot produced by [`Self::snapshot`], checking the
+    /// schema-version byte, before rebuilding a fresh `PrivateDataService`
+    /// from the restored storages. Each restored `FilterId` is passed to
+    /// [`snapshot::validate_round_trip`] with its own current `Display`
+    /// string, so that check always passes: the snapshot doesn't store the
+    /// `Display` form an id had when it was persisted, and a change to that
+    /// form between builds is not detected. Ephemeral, non-persisted state
+    /// (the query-compute cache, metrics sink, optimization policy) resets
+    /// to its default.
+    pub fn restore(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        let snapshot: PrivateDataServiceSnapshot<C, Q::Event> =
+            snapshot::from_cbor_snapshot(bytes)?;
+
+        for filter_id in snapshot.filter_storage.known_filter_ids() {
+            let expected_display = filter_id.to_string();
+            snapshot::validate_round_trip(filter_id, &expected_display)?;
+        }
+
+        Ok(Self {
+            core: PrivateDataServiceCore::new(snapshot.filter_storage),
+            event_storage: snapshot.event_storage,
+        })
+    }
 }
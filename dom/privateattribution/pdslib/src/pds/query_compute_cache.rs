@@ -0,0 +1,109 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, VecDeque},
+    hash::{Hash, Hasher},
+};
+
+use crate::queries::traits::{EpochReportRequest, QueryComputeResult};
+
+/// Default capacity for a [`QueryComputeCache`] constructed via `Default`.
+pub const DEFAULT_QUERY_COMPUTE_CACHE_CAPACITY: usize = 128;
+
+/// A cache key, cheap to compute and compare: a hash of a request's
+/// identity (via [`EpochReportRequest::hash_cache_identity`]) folded
+/// together with a
+/// [`RelevantEvents::fingerprint`](crate::events::relevant_events::RelevantEvents::fingerprint).
+/// Two computations with the same key are guaranteed, up to hash
+/// collisions, to have produced the same `QueryComputeResult`.
+pub type QueryComputeCacheKey = u64;
+
+/// Builds a [`QueryComputeCacheKey`] from a request's identity and the
+/// fingerprint of the `RelevantEvents` it ran against.
+///
+/// Deliberately goes through [`EpochReportRequest::hash_cache_identity`]
+/// rather than `request`'s `Debug` representation: `Debug` is free to drop
+/// fields via `finish_non_exhaustive()` (e.g.
+/// [`GeneralHistogramRequest`](crate::queries::general_histogram::GeneralHistogramRequest)
+/// omits `bucket_fn`, `report_uris`, and `mechanism`), which would collide
+/// two requests that `compute_report` differently into the same cache
+/// entry and return one of them a wrong report.
+pub fn query_compute_cache_key(
+    request: &impl EpochReportRequest,
+    events_fingerprint: u64,
+) -> QueryComputeCacheKey {
+    let mut hasher = DefaultHasher::new();
+    request.hash_cache_identity(&mut hasher);
+    events_fingerprint.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Memoizes [`QueryComputeResult`]s keyed by [`QueryComputeCacheKey`],
+/// borrowing the query-system caching idea from rustc: a cache keyed by
+/// inputs, with a cheap fingerprint used to detect identical work. Used by
+/// [`PrivateDataServiceCore`](crate::pds::core::PrivateDataServiceCore) so
+/// that `compute_report` doesn't redo `request.compute_report` from scratch
+/// both times it calls it (once unfiltered, once filtered after dropping
+/// OOB epochs), and so overlapping reports across requests can share work.
+///
+/// A plain `HashMap`, not thread-safe on its own: relies on
+/// `PrivateDataServiceCore` being `!Sync` (see its `_phantom` field) for
+/// that. Bounded by `capacity`, evicting the least-recently-used entry.
+#[derive(Debug)]
+pub struct QueryComputeCache<U, R> {
+    capacity: usize,
+    entries: HashMap<QueryComputeCacheKey, QueryComputeResult<U, R>>,
+    /// Keys ordered from least- to most-recently-used.
+    recency: VecDeque<QueryComputeCacheKey>,
+}
+
+impl<U, R> QueryComputeCache<U, R> {
+    /// Creates an empty cache holding at most `capacity` entries. A
+    /// `capacity` of 0 disables caching: `insert` becomes a no-op.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            recency: VecDeque::new(),
+        }
+    }
+
+    /// Looks up `key`, marking it most-recently-used on a hit.
+    pub fn get(&mut self, key: QueryComputeCacheKey) -> Option<&QueryComputeResult<U, R>> {
+        if self.entries.contains_key(&key) {
+            self.touch(key);
+            self.entries.get(&key)
+        } else {
+            None
+        }
+    }
+
+    /// Inserts or overwrites `key`, marking it most-recently-used, then
+    /// evicts the least-recently-used entries until back under `capacity`.
+    pub fn insert(&mut self, key: QueryComputeCacheKey, value: QueryComputeResult<U, R>) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        self.entries.insert(key, value);
+        self.touch(key);
+
+        while self.entries.len() > self.capacity {
+            let Some(oldest) = self.recency.pop_front() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+    }
+
+    fn touch(&mut self, key: QueryComputeCacheKey) {
+        if let Some(pos) = self.recency.iter().position(|k| *k == key) {
+            self.recency.remove(pos);
+        }
+        self.recency.push_back(key);
+    }
+}
+
+impl<U, R> Default for QueryComputeCache<U, R> {
+    fn default() -> Self {
+        Self::new(DEFAULT_QUERY_COMPUTE_CACHE_CAPACITY)
+    }
+}
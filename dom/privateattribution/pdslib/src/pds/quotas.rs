@@ -5,11 +5,14 @@ use std::{
     vec,
 };
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
-use crate::budget::traits::{Budget, FilterCapacities};
+use crate::budget::traits::{
+    Budget, EpochScopedFilterId, FilterCapacities, FilterKind, RetentionPolicy,
+};
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum FilterId<
     E = u64,    // Epoch ID
     U = String, // URI
@@ -27,6 +30,30 @@ pub enum FilterId<
     QSource(E, U /* source URI */),
 }
 
+impl<E: Copy, U> EpochScopedFilterId for FilterId<E, U> {
+    type Epoch = E;
+
+    fn epoch(&self) -> E {
+        match self {
+            FilterId::Nc(epoch_id, _)
+            | FilterId::C(epoch_id)
+            | FilterId::QTrigger(epoch_id, _)
+            | FilterId::QSource(epoch_id, _) => *epoch_id,
+        }
+    }
+}
+
+impl<E, U> FilterKind for FilterId<E, U> {
+    fn kind(&self) -> &'static str {
+        match self {
+            FilterId::Nc(_, _) => "Nc",
+            FilterId::C(_) => "C",
+            FilterId::QTrigger(_, _) => "QTrigger",
+            FilterId::QSource(_, _) => "QSource",
+        }
+    }
+}
+
 impl<E: Display, U: Display> fmt::Display for FilterId<E, U> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -47,14 +74,20 @@ impl<E: Display, U: Display> fmt::Display for FilterId<E, U> {
 }
 
 /// Struct containing the default capacity for each type of filter.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StaticCapacities<FID, B> {
     pub nc: B,
     pub c: B,
     pub qtrigger: B,
     pub qsource: B,
 
-    #[serde(skip_serializing)]
+    /// Memory-bounding policy for storages holding these capacities. `None`
+    /// by default, meaning filters are kept forever; set via
+    /// [`Self::with_retention`].
+    #[serde(default)]
+    pub retention: Option<RetentionPolicy>,
+
+    #[serde(skip, default = "std::marker::PhantomData::default")]
     _phantom: std::marker::PhantomData<FID>,
 }
 
@@ -65,15 +98,40 @@ impl<FID, B> StaticCapacities<FID, B> {
             c,
             qtrigger,
             qsource,
+            retention: None,
             _phantom: std::marker::PhantomData,
         }
     }
+
+    /// Attaches a retention policy, so a `FilterStorage` holding these
+    /// capacities can bound its memory use via `maintain`.
+    pub fn with_retention(mut self, retention: RetentionPolicy) -> Self {
+        self.retention = Some(retention);
+        self
+    }
+}
+
+/// Errors that can arise while resolving a filter's capacity, kept
+/// type-distinguishable so callers don't have to parse opaque `anyhow`
+/// strings to tell an unknown filter apart from a backend outage.
+#[derive(Error, Debug)]
+pub enum CapacityError<FID: Debug> {
+    #[error("no capacity configured for filter {0:?}")]
+    UnknownFilter(FID),
+
+    #[error("capacity lookup backend unavailable")]
+    CapacityUnavailable,
+
+    #[error("budget overflowed while computing capacity for filter {0:?}")]
+    BudgetOverflow(FID),
 }
 
-impl<B: Budget, E, U> FilterCapacities for StaticCapacities<FilterId<E, U>, B> {
+impl<B: Budget, E: Debug, U: Debug> FilterCapacities
+    for StaticCapacities<FilterId<E, U>, B>
+{
     type FilterId = FilterId<E, U>;
     type Budget = B;
-    type Error = anyhow::Error;
+    type Error = CapacityError<FilterId<E, U>>;
 
     fn capacity(
         &self,
@@ -86,6 +144,10 @@ impl<B: Budget, E, U> FilterCapacities for StaticCapacities<FilterId<E, U>, B> {
             FilterId::QSource(..) => Ok(self.qsource.clone()),
         }
     }
+
+    fn retention(&self) -> Option<&RetentionPolicy> {
+        self.retention.as_ref()
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
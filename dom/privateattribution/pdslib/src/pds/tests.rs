@@ -2,8 +2,16 @@ use std::collections::HashMap;
 
 use super::quotas::{FilterId::*, *};
 use crate::{
-    budget::{pure_dp_filter::PureDPBudget, traits::FilterStorage},
-    events::{ppa_event::PpaEvent, traits::EventUris},
+    budget::{
+        pure_dp_filter::PureDPBudget,
+        traits::{FilterStorage, RetentionPolicy},
+    },
+    events::{
+        event_key::EventKey,
+        ppa_event::PpaEvent,
+        simple_event::SimpleEvent,
+        traits::{EventStorage, EventUris},
+    },
     pds::aliases::{
         PpaEventStorage, PpaFilterStorage, PpaPds, SimpleEventStorage,
         SimpleFilterStorage, SimplePds,
@@ -394,3 +402,355 @@ fn test_cross_report_optimization() -> Result<(), anyhow::Error> {
     }
     Ok(())
 }
+
+/// A pruned epoch must behave exactly like an epoch that was never touched:
+/// no relevant events, and fresh, full-capacity filters.
+#[test]
+fn test_prune_makes_epoch_indistinguishable_from_never_created(
+) -> Result<(), anyhow::Error> {
+    let capacities: StaticCapacities<FilterId, PureDPBudget> =
+        StaticCapacities::mock().with_retention(RetentionPolicy {
+            window_epochs: 1,
+            max_live_filters: 100,
+        });
+    let filters = PpaFilterStorage::new(capacities)?;
+    let events = PpaEventStorage::new();
+    let mut pds = PpaPds::<_>::new(filters, events);
+
+    let event_uris = EventUris::mock();
+    let report_request_uris = ReportRequestUris::mock();
+
+    // Register an event in epoch 1, and spend some passive budget there too,
+    // so both storages have state to prune.
+    let stale_epoch = 1;
+    pds.register_event(PpaEvent {
+        id: 1,
+        timestamp: 100,
+        epoch_number: stale_epoch,
+        histogram_index: 0,
+        uris: event_uris.clone(),
+        filter_data: 1,
+    })?;
+    pds.account_for_passive_privacy_loss(PassivePrivacyLossRequest {
+        epoch_ids: vec![stale_epoch],
+        privacy_budget: PureDPBudget::from(0.5),
+        uris: report_request_uris.clone(),
+    })?;
+
+    let config = PpaHistogramConfig {
+        start_epoch: stale_epoch,
+        end_epoch: stale_epoch,
+        attributable_value: 100.0,
+        max_attributable_value: 200.0,
+        requested_epsilon: 1.0,
+        histogram_size: 4,
+    };
+    let make_request = || {
+        PpaHistogramRequest::new(
+            config.clone(),
+            PpaRelevantEventSelector {
+                report_request_uris: report_request_uris.clone(),
+                is_matching_event: Box::new(|event_filter_data: u64| {
+                    event_filter_data == 1
+                }),
+                bucket_intermediary_mapping: HashMap::new(),
+            },
+        )
+        .map_err(|e| anyhow::anyhow!("Failed to create request: {}", e))
+    };
+
+    // Before pruning, the registered event is picked up: the beneficiary's
+    // NC filter already shows the passive-loss deduction.
+    let beneficiary_filter_id =
+        FilterId::Nc(stale_epoch, report_request_uris.querier_uris[0].clone());
+    assert_eq!(
+        pds.core
+            .filter_storage
+            .remaining_budget(&beneficiary_filter_id)?,
+        PureDPBudget::from(0.5),
+        "Passive loss should have been deducted before pruning"
+    );
+
+    // Advance far enough that `stale_epoch` falls outside the retention
+    // window, then prune.
+    let now_epoch = stale_epoch + 10;
+    let (filters_dropped, epochs_dropped) = pds.prune(now_epoch)?;
+    assert!(filters_dropped > 0, "Expected stale filters to be dropped");
+    assert_eq!(
+        epochs_dropped, 1,
+        "Expected the stale epoch's events to be dropped"
+    );
+
+    // After pruning, the NC filter is back at full capacity, as if the
+    // epoch had never been touched.
+    assert_eq!(
+        pds.core
+            .filter_storage
+            .remaining_budget(&beneficiary_filter_id)?,
+        PureDPBudget::from(1.0),
+        "Pruned filter should be indistinguishable from a fresh one"
+    );
+
+    // And a report computed over the pruned epoch sees no events at all,
+    // matching a request for an epoch that was never created.
+    let request = make_request()?;
+    let report_result = pds.compute_report(&request)?;
+    for report in report_result.values() {
+        assert!(
+            report.filtered_report.bin_values.is_empty(),
+            "Pruned epoch should contribute no events to the report"
+        );
+    }
+
+    Ok(())
+}
+
+/// `compute_reports` must apply requests in submission order against the
+/// same shared filters, so a later request can be starved by budget an
+/// earlier request in the same batch already consumed.
+#[test]
+fn test_compute_reports_shares_budget_across_the_batch(
+) -> Result<(), anyhow::Error> {
+    let capacities: StaticCapacities<FilterId, PureDPBudget> =
+        StaticCapacities::mock(); // nc = 1.0
+    let filters = PpaFilterStorage::new(capacities)?;
+    let events = PpaEventStorage::new();
+    let mut pds = PpaPds::<_>::new(filters, events);
+
+    let event_uris = EventUris::mock();
+    let mut report_request_uris = ReportRequestUris::mock();
+    // No intermediaries, so the default `OptimizationPolicy` keeps the
+    // per-querier report instead of taking the optimization path.
+    report_request_uris.intermediary_uris.clear();
+    let epoch = 1;
+
+    pds.register_event(PpaEvent {
+        id: 1,
+        timestamp: 100,
+        epoch_number: epoch,
+        histogram_index: 0,
+        uris: event_uris.clone(),
+        filter_data: 1,
+    })?;
+
+    // Each request on its own would deduct 0.5 from the beneficiary's NC
+    // filter (capacity 1.0), so the third in the batch should be starved by
+    // the first two.
+    let config = PpaHistogramConfig {
+        start_epoch: epoch,
+        end_epoch: epoch,
+        attributable_value: 100.0,
+        max_attributable_value: 100.0,
+        requested_epsilon: 1.0,
+        histogram_size: 4,
+    };
+    let make_request = || {
+        PpaHistogramRequest::new(
+            config.clone(),
+            PpaRelevantEventSelector {
+                report_request_uris: report_request_uris.clone(),
+                is_matching_event: Box::new(|event_filter_data: u64| {
+                    event_filter_data == 1
+                }),
+                bucket_intermediary_mapping: HashMap::new(),
+            },
+        )
+        .map_err(|e| anyhow::anyhow!("Failed to create request: {}", e))
+    };
+
+    let requests =
+        vec![make_request()?, make_request()?, make_request()?];
+    let results = pds.compute_reports(requests)?;
+    assert_eq!(results.len(), 3, "Expected one result per request");
+
+    let beneficiary_filter_id =
+        FilterId::Nc(epoch, report_request_uris.querier_uris[0].clone());
+
+    for (i, report_map) in results.iter().enumerate() {
+        let report = report_map
+            .values()
+            .next()
+            .expect("Expected a report for the sole querier");
+        if i < 2 {
+            assert!(
+                report.oob_filters.is_empty(),
+                "Request {i} should have succeeded"
+            );
+        } else {
+            assert!(
+                report.oob_filters.contains(&beneficiary_filter_id),
+                "Request {i} should have been starved by the earlier requests in the batch"
+            );
+        }
+    }
+
+    assert_eq!(
+        pds.core
+            .filter_storage
+            .remaining_budget(&beneficiary_filter_id)?,
+        PureDPBudget::from(0.0),
+        "Exactly the first two requests' budget should have been consumed"
+    );
+
+    Ok(())
+}
+
+/// `PrivateDataService::snapshot`/`restore` must carry over both consumed
+/// filter budget and registered events, so a device that restarts picks up
+/// exactly where it left off instead of, say, forgetting events but
+/// remembering budget (which would under-count privacy loss on future
+/// reports).
+#[test]
+fn test_snapshot_restores_both_filter_budget_and_events(
+) -> Result<(), anyhow::Error> {
+    let capacities: StaticCapacities<FilterId, PureDPBudget> =
+        StaticCapacities::mock();
+    let filters = SimpleFilterStorage::new(capacities)?;
+    let events = SimpleEventStorage::new();
+    let mut pds = SimplePds::new(filters, events);
+
+    let uris = ReportRequestUris::mock();
+    pds.account_for_passive_privacy_loss(PassivePrivacyLossRequest {
+        epoch_ids: vec![1],
+        privacy_budget: PureDPBudget::from(0.5),
+        uris: uris.clone(),
+    })?;
+    pds.register_event(SimpleEvent {
+        id: 1,
+        epoch_number: 1,
+        event_key: EventKey::default(),
+        uris: EventUris::mock(),
+    })?;
+
+    let bytes = pds.snapshot()?;
+    let mut restored = SimplePds::restore(&bytes)?;
+
+    let expected_budgets = vec![
+        (FilterId::Nc(1, uris.querier_uris[0].clone()), 0.5),
+        (FilterId::C(1), 19.5),
+        (FilterId::QTrigger(1, uris.trigger_uri.clone()), 1.0),
+    ];
+    assert_remaining_budgets(
+        &mut restored.core.filter_storage,
+        &expected_budgets,
+    )?;
+
+    let restored_events: Vec<_> = restored.event_storage.events_for_epoch(&1)?.collect();
+    assert_eq!(restored_events.len(), 1);
+    assert_eq!(restored_events[0].id, 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_restore_rejects_unsupported_snapshot_version() -> Result<(), anyhow::Error> {
+    let capacities: StaticCapacities<FilterId, PureDPBudget> =
+        StaticCapacities::mock();
+    let filters = SimpleFilterStorage::new(capacities)?;
+    let events = SimpleEventStorage::new();
+    let pds = SimplePds::new(filters, events);
+
+    let mut bytes = pds.snapshot()?;
+    bytes[0] = crate::budget::snapshot::SNAPSHOT_SCHEMA_VERSION + 1;
+
+    let err = SimplePds::restore(&bytes).unwrap_err();
+    assert!(matches!(
+        err,
+        crate::budget::snapshot::SnapshotError::UnsupportedVersion { .. }
+    ));
+
+    Ok(())
+}
+
+/// `compute_reports_batch` must key results by each request's position in
+/// the input slice, and one request running out of budget must not prevent
+/// the others (which target different queriers, and so different `Nc`
+/// filters) from succeeding.
+#[test]
+fn test_compute_reports_batch_keys_by_request_id_and_isolates_oob(
+) -> Result<(), anyhow::Error> {
+    let capacities: StaticCapacities<FilterId, PureDPBudget> =
+        StaticCapacities::mock(); // nc = 1.0
+    let filters = PpaFilterStorage::new(capacities)?;
+    let events = PpaEventStorage::new();
+    let mut pds = PpaPds::<_>::new(filters, events);
+
+    let mut event_uris = EventUris::mock();
+    event_uris.querier_uris =
+        vec!["querier-a.com".to_string(), "querier-b.com".to_string()];
+    let epoch = 1;
+
+    pds.register_event(PpaEvent {
+        id: 1,
+        timestamp: 100,
+        epoch_number: epoch,
+        histogram_index: 0,
+        uris: event_uris.clone(),
+        filter_data: 1,
+    })?;
+
+    let config = PpaHistogramConfig {
+        start_epoch: epoch,
+        end_epoch: epoch,
+        attributable_value: 100.0,
+        max_attributable_value: 100.0,
+        requested_epsilon: 1.0,
+        histogram_size: 4,
+    };
+    let make_request = |querier_uri: &str| {
+        let mut report_request_uris = ReportRequestUris::mock();
+        report_request_uris.querier_uris = vec![querier_uri.to_string()];
+        // No intermediaries, so each request keeps its per-querier report.
+        report_request_uris.intermediary_uris.clear();
+        PpaHistogramRequest::new(
+            config.clone(),
+            PpaRelevantEventSelector {
+                report_request_uris,
+                is_matching_event: Box::new(|event_filter_data: u64| {
+                    event_filter_data == 1
+                }),
+                bucket_intermediary_mapping: HashMap::new(),
+            },
+        )
+        .map_err(|e| anyhow::anyhow!("Failed to create request: {}", e))
+    };
+
+    // Two distinct queriers, each with their own `Nc` filter (capacity
+    // 1.0), both sharing the same epoch's events: the dedup cache should
+    // fetch that epoch's events from storage once and reuse it for both.
+    let requests = vec![
+        make_request("querier-a.com")?,
+        make_request("querier-b.com")?,
+    ];
+    let results = pds.compute_reports_batch(&requests)?;
+
+    assert_eq!(results.len(), 2, "Expected one result per request id");
+    for request_id in 0..requests.len() {
+        let report_map = results
+            .get(&request_id)
+            .unwrap_or_else(|| panic!("Expected a result for request {request_id}"));
+        let report = report_map
+            .values()
+            .next()
+            .expect("Expected a report for the sole querier");
+        assert!(
+            report.oob_filters.is_empty(),
+            "Request {request_id} targets its own querier's filter and should have succeeded"
+        );
+    }
+
+    assert_eq!(
+        pds.core
+            .filter_storage
+            .remaining_budget(&FilterId::Nc(epoch, "querier-a.com".to_string()))?,
+        PureDPBudget::from(0.5),
+    );
+    assert_eq!(
+        pds.core
+            .filter_storage
+            .remaining_budget(&FilterId::Nc(epoch, "querier-b.com".to_string()))?,
+        PureDPBudget::from(0.5),
+    );
+
+    Ok(())
+}
@@ -0,0 +1,164 @@
+use std::{collections::HashMap, fmt::Debug, hash::Hash};
+
+use crate::{events::traits::EventUris, queries::traits::ReportRequestUris};
+
+/// A small `Copy` handle standing in for a URI that's been interned by a
+/// [`UriInterner`]. `FilterId` is already generic over its URI type (see
+/// [`FilterId`](crate::pds::quotas::FilterId)), so `FilterId<EpochId, UriId>`
+/// drops straight in as the hot-path map key: comparing and hashing a
+/// `UriId` is a single `u32` operation instead of a full string comparison
+/// and hash, and storing it in a `FilterId` avoids cloning the underlying
+/// URI on every lookup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UriId(u32);
+
+/// Maps URIs to small, `Copy`, constant-time-hashable [`UriId`] handles.
+///
+/// Each distinct URI is assigned a handle the first time it's interned;
+/// interning it again returns the same handle. Handles can be resolved back
+/// to the original URI, mainly for debugging and test assertions, since
+/// nothing on the budgeting hot path needs to go back from a handle to a
+/// URI.
+#[derive(Debug, Default)]
+pub struct UriInterner<U> {
+    ids: HashMap<U, UriId>,
+    uris: Vec<U>,
+}
+
+impl<U> UriInterner<U>
+where
+    U: Clone + Eq + Hash,
+{
+    pub fn new() -> Self {
+        Self {
+            ids: HashMap::new(),
+            uris: Vec::new(),
+        }
+    }
+
+    /// Returns the handle for `uri`, assigning it a fresh one if this is the
+    /// first time it's been interned.
+    pub fn intern(&mut self, uri: U) -> UriId {
+        if let Some(&id) = self.ids.get(&uri) {
+            return id;
+        }
+
+        let id = UriId(self.uris.len() as u32);
+        self.uris.push(uri.clone());
+        self.ids.insert(uri, id);
+        id
+    }
+
+    /// Resolves a handle back to the URI it was interned from, or `None` if
+    /// `id` wasn't produced by this interner.
+    pub fn resolve(&self, id: UriId) -> Option<&U> {
+        self.uris.get(id.0 as usize)
+    }
+
+    /// The handle already assigned to `uri`, without interning it.
+    pub fn get(&self, uri: &U) -> Option<UriId> {
+        self.ids.get(uri).copied()
+    }
+
+    /// Interns every URI embedded in a [`ReportRequestUris`], so a report
+    /// request only has to pay the interning cost once, at request time,
+    /// instead of once per filter lookup downstream.
+    pub fn intern_report_uris(
+        &mut self,
+        uris: &ReportRequestUris<U>,
+    ) -> ReportRequestUris<UriId> {
+        ReportRequestUris {
+            trigger_uri: self.intern(uris.trigger_uri.clone()),
+            source_uris: uris
+                .source_uris
+                .iter()
+                .cloned()
+                .map(|uri| self.intern(uri))
+                .collect(),
+            intermediary_uris: uris
+                .intermediary_uris
+                .iter()
+                .cloned()
+                .map(|uri| self.intern(uri))
+                .collect(),
+            querier_uris: uris
+                .querier_uris
+                .iter()
+                .cloned()
+                .map(|uri| self.intern(uri))
+                .collect(),
+        }
+    }
+
+    /// Interns every URI embedded in an [`EventUris`], so events only pay
+    /// the interning cost once, at registration time.
+    pub fn intern_event_uris(&mut self, uris: &EventUris<U>) -> EventUris<UriId> {
+        EventUris {
+            source_uri: self.intern(uris.source_uri.clone()),
+            trigger_uris: uris
+                .trigger_uris
+                .iter()
+                .cloned()
+                .map(|uri| self.intern(uri))
+                .collect(),
+            intermediary_uris: uris
+                .intermediary_uris
+                .iter()
+                .cloned()
+                .map(|uri| self.intern(uri))
+                .collect(),
+            querier_uris: uris
+                .querier_uris
+                .iter()
+                .cloned()
+                .map(|uri| self.intern(uri))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interning_the_same_uri_twice_returns_the_same_handle() {
+        let mut interner: UriInterner<String> = UriInterner::new();
+
+        let first = interner.intern("blog.com".to_string());
+        let second = interner.intern("blog.com".to_string());
+        let other = interner.intern("shoes.com".to_string());
+
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    fn test_resolve_round_trips_back_to_the_original_uri() {
+        let mut interner: UriInterner<String> = UriInterner::new();
+        let id = interner.intern("blog.com".to_string());
+
+        assert_eq!(interner.resolve(id), Some(&"blog.com".to_string()));
+    }
+
+    #[test]
+    fn test_intern_report_uris_reuses_handles_for_shared_uris() {
+        let mut interner: UriInterner<String> = UriInterner::new();
+        let uris = ReportRequestUris {
+            trigger_uri: "shoes.com".to_string(),
+            source_uris: vec!["blog.com".to_string()],
+            intermediary_uris: vec!["search.engine".to_string()],
+            querier_uris: vec!["shoes.com".to_string()],
+        };
+
+        let interned = interner.intern_report_uris(&uris);
+
+        // trigger_uri and querier_uris[0] are the same URI, so they must
+        // intern to the same handle.
+        assert_eq!(interned.trigger_uri, interned.querier_uris[0]);
+        assert_eq!(
+            interner.resolve(interned.source_uris[0]),
+            Some(&"blog.com".to_string())
+        );
+    }
+}
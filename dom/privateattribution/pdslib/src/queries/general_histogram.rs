@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+
+use crate::{
+    budget::pure_dp_filter::PureDPBudget,
+    events::{relevant_events::RelevantEvents, simple_event::SimpleEvent},
+    mechanisms::{MechanismChoice, NoiseScale, NormType},
+    queries::{
+        histogram::{HistogramReport, HistogramRequest},
+        simple_last_touch_histogram::SimpleRelevantEventSelector,
+        traits::{EpochReportRequest, QueryComputeResult, ReportRequestUris},
+    },
+};
+
+/// Which relevant events contribute to the report. Unlike
+/// [`AttributionLogic`](crate::queries::ppa_histogram::AttributionLogic),
+/// which splits one `attributable_value` across events, here each event
+/// already carries its own bucket and value (via `bucket_fn`), so the
+/// attribution logic only decides *which* relevant events are included,
+/// not how to divide a shared pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GeneralAttributionLogic {
+    /// Only the single most recent relevant event, across all epochs.
+    LastTouch,
+    /// Only the single oldest relevant event, across all epochs.
+    FirstTouch,
+    /// Every relevant event, each contributing its own bucket and value.
+    Uniform,
+}
+
+/// A general multi-bin histogram query over [`SimpleEvent`]s, generalizing
+/// [`SimpleLastTouchHistogramRequest`](crate::queries::simple_last_touch_histogram::SimpleLastTouchHistogramRequest):
+/// instead of a hard-coded single bin keyed by `event_key`, `bucket_fn` maps
+/// each event to an arbitrary bucket key and per-bucket value, and
+/// `attribution_logic` selects which relevant events are attributed.
+///
+/// Report computation and sensitivity are delegated to the generic
+/// [`HistogramRequest`] machinery, so `single_epoch_individual_sensitivity`
+/// computes real L1/L2 norms over every populated bin rather than assuming
+/// a single nonzero bin.
+pub struct GeneralHistogramRequest {
+    pub epoch_start: u64,
+    pub epoch_end: u64,
+
+    /// Maximum value (sum) attributable across all bins for this
+    /// conversion.
+    pub attributable_value: f64,
+
+    /// Which mechanism to request noise from: Laplace (ε-DP, L1
+    /// sensitivity) or Gaussian (zCDP, L2 sensitivity), calibrated against
+    /// this request's global sensitivity (see `report_global_sensitivity`).
+    pub mechanism: MechanismChoice,
+
+    /// Maps a relevant event to the bucket key and value it would
+    /// contribute, were it selected by `attribution_logic`.
+    pub bucket_fn: Box<dyn Fn(&SimpleEvent) -> (u64, f64)>,
+    pub attribution_logic: GeneralAttributionLogic,
+
+    pub relevant_event_selector: SimpleRelevantEventSelector,
+    pub report_uris: ReportRequestUris<String>,
+}
+
+impl std::fmt::Debug for GeneralHistogramRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GeneralHistogramRequest")
+            .field("epoch_start", &self.epoch_start)
+            .field("epoch_end", &self.epoch_end)
+            .field("attributable_value", &self.attributable_value)
+            .field("attribution_logic", &self.attribution_logic)
+            .finish_non_exhaustive()
+    }
+}
+
+impl HistogramRequest for GeneralHistogramRequest {
+    type BucketKey = u64;
+    type HistogramEvent = SimpleEvent;
+    type HistogramEpochId = u64;
+    type HistogramUri = String;
+
+    fn attributable_value(&self) -> f64 {
+        self.attributable_value
+    }
+
+    fn bucket_key(&self, event: &SimpleEvent) -> u64 {
+        (self.bucket_fn)(event).0
+    }
+
+    fn event_values<'a>(
+        &self,
+        relevant_events: &'a RelevantEvents<SimpleEvent>,
+    ) -> Vec<(&'a SimpleEvent, f64)> {
+        match self.attribution_logic {
+            GeneralAttributionLogic::LastTouch => {
+                // Most recent epoch first; within an epoch, most recent
+                // event first.
+                for epoch_id in self.epoch_ids() {
+                    if let Some(event) =
+                        relevant_events.for_epoch(&epoch_id).last()
+                    {
+                        let (_, value) = (self.bucket_fn)(event);
+                        return vec![(event, value)];
+                    }
+                }
+                vec![]
+            }
+
+            GeneralAttributionLogic::FirstTouch => {
+                // Oldest epoch first; within an epoch, oldest event first.
+                for epoch_id in self.epoch_start..=self.epoch_end {
+                    if let Some(event) =
+                        relevant_events.for_epoch(&epoch_id).first()
+                    {
+                        let (_, value) = (self.bucket_fn)(event);
+                        return vec![(event, value)];
+                    }
+                }
+                vec![]
+            }
+
+            GeneralAttributionLogic::Uniform => {
+                let mut events = Vec::new();
+                for epoch_id in self.epoch_start..=self.epoch_end {
+                    for event in relevant_events.for_epoch(&epoch_id) {
+                        let (_, value) = (self.bucket_fn)(event);
+                        events.push((event, value));
+                    }
+                }
+                events
+            }
+        }
+    }
+
+    fn histogram_report_uris(&self) -> ReportRequestUris<String> {
+        self.report_uris.clone()
+    }
+
+    fn get_bucket_intermediary_mapping(&self) -> Option<&HashMap<u64, String>> {
+        // Unlike `PpaHistogramRequest`, this query doesn't track a
+        // bucket -> intermediary mapping, so `filter_report_for_intermediary`
+        // returns `None` and each intermediary gets an empty report.
+        None
+    }
+
+    fn filter_report_for_intermediary(
+        &self,
+        _report: &HistogramReport<u64>,
+        _intermediary_uri: &String,
+        _relevant_events_per_epoch: &RelevantEvents<SimpleEvent>,
+    ) -> Option<HistogramReport<u64>> {
+        None
+    }
+}
+
+impl EpochReportRequest for GeneralHistogramRequest {
+    type Uri = String;
+    type EpochId = u64;
+    type Event = SimpleEvent;
+    type RelevantEventSelector = SimpleRelevantEventSelector;
+    type PrivacyBudget = PureDPBudget;
+    type Report = HistogramReport<u64>;
+
+    fn epoch_ids(&self) -> Vec<Self::EpochId> {
+        (self.epoch_start..=self.epoch_end).rev().collect()
+    }
+
+    fn report_global_sensitivity(&self) -> f64 {
+        self.histogram_report_global_sensitivity()
+    }
+
+    fn relevant_event_selector(&self) -> &Self::RelevantEventSelector {
+        &self.relevant_event_selector
+    }
+
+    fn report_uris(&self) -> &ReportRequestUris<Self::Uri> {
+        &self.report_uris
+    }
+
+    fn hash_cache_identity(&self, hasher: &mut dyn std::hash::Hasher) {
+        use std::hash::Hash;
+        self.epoch_start.hash(hasher);
+        self.epoch_end.hash(hasher);
+        self.attributable_value.to_bits().hash(hasher);
+        self.mechanism.hash_into(hasher);
+        self.attribution_logic.hash(hasher);
+        // `bucket_fn` is a `Box<dyn Fn>`, which has no structural identity
+        // to hash -- two closures with identical behavior aren't
+        // necessarily "the same" bucket_fn and vice versa. Hashing the
+        // trait object's full pointer (data address and vtable, since
+        // zero-sized closures all share one dangling data address) at least
+        // ensures two *different* `GeneralHistogramRequest`s (built from
+        // separate closures, as every caller does) never collide into the
+        // same cache entry, which is what actually matters here: a false
+        // cache miss just recomputes the report, while a false cache hit
+        // would return a wrong one.
+        (self.bucket_fn.as_ref() as *const _).hash(hasher);
+        self.relevant_event_selector.lambda.hash(hasher);
+        self.report_uris.hash_into(hasher);
+    }
+
+    fn compute_report(
+        &self,
+        relevant_events: &RelevantEvents<Self::Event>,
+    ) -> QueryComputeResult<Self::Uri, Self::Report> {
+        self.compute_histogram_report(relevant_events)
+    }
+
+    fn single_epoch_individual_sensitivity(
+        &self,
+        report: &Self::Report,
+        norm_type: NormType,
+    ) -> f64 {
+        self.histogram_single_epoch_individual_sensitivity(report, norm_type)
+    }
+
+    fn single_epoch_source_individual_sensitivity(
+        &self,
+        report: &Self::Report,
+        norm_type: NormType,
+    ) -> f64 {
+        self.histogram_single_epoch_source_individual_sensitivity(
+            report, norm_type,
+        )
+    }
+
+    fn noise_scale(&self) -> NoiseScale {
+        self.mechanism.noise_scale(self.report_global_sensitivity())
+    }
+}
@@ -12,6 +12,12 @@ use crate::{
 #[derive(Debug, Clone)]
 pub struct HistogramReport<BucketKey> {
     pub bin_values: HashMap<BucketKey, f64>,
+
+    /// Set by `compute_histogram_report` when the contribution cap
+    /// (`attributable_value`) was hit and attribution stopped early, leaving
+    /// some relevant events unattributed. Exposed so callers (e.g. metrics)
+    /// can track how often the cap is the limiting factor.
+    pub early_stop: bool,
 }
 
 /// Trait for bucket keys.
@@ -25,6 +31,7 @@ impl<BK> Default for HistogramReport<BK> {
     fn default() -> Self {
         Self {
             bin_values: HashMap::new(),
+            early_stop: false,
         }
     }
 }
@@ -107,6 +114,7 @@ where
         // ordering the events from `relevant_events`.
         let mut report = HistogramReport {
             bin_values: HashMap::new(),
+            early_stop: false,
         };
         let mut early_stop = false;
 
@@ -117,6 +125,7 @@ where
                 early_stop = true;
                 report = HistogramReport {
                     bin_values: bin_values.clone(),
+                    early_stop,
                 };
                 break;
             }
@@ -125,7 +134,10 @@ where
         }
 
         if !early_stop {
-            report = HistogramReport { bin_values };
+            report = HistogramReport {
+                bin_values,
+                early_stop,
+            };
         }
 
         let mut site_to_report_mapping = HashMap::new();
@@ -151,6 +163,7 @@ where
                         intermediary_uri.clone(),
                         HistogramReport {
                             bin_values: HashMap::new(),
+                            early_stop: report.early_stop,
                         },
                     );
                 }
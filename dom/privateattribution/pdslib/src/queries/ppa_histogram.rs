@@ -69,7 +69,51 @@ pub struct DirectPpaHistogramConfig {
 
 #[derive(Debug, Clone)]
 pub enum AttributionLogic {
+    /// All of `attributable_value` to the single most recent relevant event,
+    /// across all epochs.
     LastTouch,
+
+    /// All of `attributable_value` to the single oldest relevant event,
+    /// across all epochs.
+    FirstTouch,
+
+    /// `attributable_value` split evenly across every valid relevant event.
+    EqualCredit,
+
+    /// `first`/`last` fractions of `attributable_value` go to the oldest
+    /// and most recent valid events respectively; the remaining
+    /// `1.0 - first - last` fraction splits evenly among the events in
+    /// between. With only one valid event, it receives the full
+    /// `attributable_value`.
+    PositionBased { first: f64, last: f64 },
+
+    /// Each valid event is weighted by
+    /// `2^(-age_in_epochs / half_life_epochs)`, where age is measured in
+    /// epochs relative to `end_epoch`; weights are then normalized so they
+    /// sum to `attributable_value`.
+    TimeDecay { half_life_epochs: f64 },
+}
+
+impl AttributionLogic {
+    /// Hashes this choice, for
+    /// [`PpaHistogramRequest::hash_cache_identity`]. Can't `#[derive(Hash)]`
+    /// directly since `PositionBased`/`TimeDecay` carry `f64` fields;
+    /// hashed via their bits instead, same approach as
+    /// [`MechanismChoice::hash_into`](crate::mechanisms::MechanismChoice::hash_into).
+    pub fn hash_into(&self, hasher: &mut dyn std::hash::Hasher) {
+        use std::hash::Hash;
+        std::mem::discriminant(self).hash(hasher);
+        match self {
+            Self::LastTouch | Self::FirstTouch | Self::EqualCredit => {}
+            Self::PositionBased { first, last } => {
+                first.to_bits().hash(hasher);
+                last.to_bits().hash(hasher);
+            }
+            Self::TimeDecay { half_life_epochs } => {
+                half_life_epochs.to_bits().hash(hasher);
+            }
+        }
+    }
 }
 
 impl<U: Uri> RelevantEventSelector for PpaRelevantEventSelector<U> {
@@ -182,6 +226,38 @@ impl<U: Uri> PpaHistogramRequest<U> {
         })
     }
 
+    /// Overrides the attribution logic used to split `attributable_value`
+    /// across relevant events. Defaults to [`AttributionLogic::LastTouch`].
+    pub fn with_attribution_logic(mut self, logic: AttributionLogic) -> Self {
+        self.logic = logic;
+        self
+    }
+
+    /// Collects every relevant event across `start_epoch..=end_epoch`, in
+    /// chronological order (oldest first), paired with its epoch id. Events
+    /// with an out-of-range bucket index are dropped (and logged), matching
+    /// the [`AttributionLogic::LastTouch`] drop behavior.
+    fn collect_valid_events_chronological<'a>(
+        &self,
+        relevant_events: &'a RelevantEvents<PpaEvent<U>>,
+    ) -> Vec<(PpaEpochId, &'a PpaEvent<U>)> {
+        let mut events = Vec::new();
+        for epoch_id in self.start_epoch..=self.end_epoch {
+            for event in relevant_events.for_epoch(&epoch_id) {
+                if event.histogram_index < self.histogram_size {
+                    events.push((epoch_id, event));
+                } else {
+                    log::error!(
+                        "Dropping event with id {} due to invalid bucket key {}",
+                        event.id,
+                        event.histogram_index
+                    );
+                }
+            }
+        }
+        events
+    }
+
     pub fn get_bucket_intermediary_mapping(&self) -> &HashMap<u64, U> {
         &self.relevant_event_selector.bucket_intermediary_mapping
     }
@@ -227,8 +303,7 @@ impl<U: Uri> HistogramRequest for PpaHistogramRequest<U> {
         &self,
         relevant_events: &'a RelevantEvents<PpaEvent<U>>,
     ) -> Vec<(&'a PpaEvent<U>, f64)> {
-        // Supporting only one attribution logic for now.
-        match self.logic {
+        match &self.logic {
             // Attribute all the value to the most recent relevant event, across
             // all epochs
             AttributionLogic::LastTouch => {
@@ -259,11 +334,102 @@ impl<U: Uri> HistogramRequest for PpaHistogramRequest<U> {
                         }
                     }
                 }
+
+                vec![]
             }
-        }
 
-        // If no valid event was found, return an empty vector.
-        vec![]
+            // Attribute all the value to the single oldest relevant event,
+            // across all epochs.
+            AttributionLogic::FirstTouch => {
+                match self
+                    .collect_valid_events_chronological(relevant_events)
+                    .first()
+                {
+                    Some(&(_, event)) => vec![(event, self.attributable_value)],
+                    None => vec![],
+                }
+            }
+
+            // Split `attributable_value` evenly across every valid relevant
+            // event.
+            AttributionLogic::EqualCredit => {
+                let events =
+                    self.collect_valid_events_chronological(relevant_events);
+                if events.is_empty() {
+                    return vec![];
+                }
+
+                let share = self.attributable_value / events.len() as f64;
+                events
+                    .into_iter()
+                    .map(|(_, event)| (event, share))
+                    .collect()
+            }
+
+            // `first`/`last` fractions of `attributable_value` go to the
+            // oldest/most recent valid events; the remainder splits evenly
+            // among the events in between.
+            AttributionLogic::PositionBased { first, last } => {
+                let events =
+                    self.collect_valid_events_chronological(relevant_events);
+                match events.len() {
+                    0 => vec![],
+                    1 => vec![(events[0].1, self.attributable_value)],
+                    n => {
+                        let middle_count = n - 2;
+                        let middle_share = if middle_count > 0 {
+                            (1.0 - first - last).max(0.0) / middle_count as f64
+                        } else {
+                            0.0
+                        };
+
+                        events
+                            .into_iter()
+                            .enumerate()
+                            .map(|(i, (_, event))| {
+                                let fraction = if i == 0 {
+                                    *first
+                                } else if i == n - 1 {
+                                    *last
+                                } else {
+                                    middle_share
+                                };
+                                (event, fraction * self.attributable_value)
+                            })
+                            .collect()
+                    }
+                }
+            }
+
+            // Weight each valid event by its age relative to `end_epoch`,
+            // then normalize the weights so they sum to
+            // `attributable_value`.
+            AttributionLogic::TimeDecay { half_life_epochs } => {
+                let events =
+                    self.collect_valid_events_chronological(relevant_events);
+                if events.is_empty() {
+                    return vec![];
+                }
+
+                let half_life = half_life_epochs.max(f64::MIN_POSITIVE);
+                let weights: Vec<f64> = events
+                    .iter()
+                    .map(|(epoch_id, _)| {
+                        let age = (self.end_epoch - epoch_id) as f64;
+                        2f64.powf(-age / half_life)
+                    })
+                    .collect();
+                let total_weight: f64 = weights.iter().sum();
+
+                events
+                    .into_iter()
+                    .zip(weights)
+                    .map(|((_, event), weight)| {
+                        (event, self.attributable_value * weight / total_weight)
+                    })
+                    .collect()
+            }
+        }
     }
 
     fn get_bucket_intermediary_mapping(&self) -> Option<&HashMap<u64, U>> {
@@ -300,6 +466,7 @@ impl<U: Uri> HistogramRequest for PpaHistogramRequest<U> {
             );
             Some(HistogramReport {
                 bin_values: filtered_bins,
+                early_stop: report.early_stop,
             })
         }
     }
@@ -337,6 +504,31 @@ impl<U: Uri> EpochReportRequest for PpaHistogramRequest<U> {
         &self.relevant_event_selector.report_request_uris
     }
 
+    fn hash_cache_identity(&self, hasher: &mut dyn std::hash::Hasher) {
+        use std::hash::Hash;
+        self.start_epoch.hash(hasher);
+        self.end_epoch.hash(hasher);
+        self.attributable_value.to_bits().hash(hasher);
+        self.laplace_noise_scale.to_bits().hash(hasher);
+        self.histogram_size.hash(hasher);
+        self.logic.hash_into(hasher);
+        self.relevant_event_selector
+            .report_request_uris
+            .hash_into(hasher);
+        // `is_matching_event` is a `Box<dyn Fn>`, with no structural
+        // identity to hash -- see the identical reasoning in
+        // `GeneralHistogramRequest::hash_cache_identity`.
+        (self.relevant_event_selector.is_matching_event.as_ref() as *const _)
+            .hash(hasher);
+        let mut mapping: Vec<_> = self
+            .relevant_event_selector
+            .bucket_intermediary_mapping
+            .iter()
+            .collect();
+        mapping.sort_by_key(|(bucket, _)| *bucket);
+        mapping.hash(hasher);
+    }
+
     fn compute_report(
         &self,
         relevant_events: &RelevantEvents<Self::Event>,
@@ -6,7 +6,7 @@ use crate::{
         hashmap_event_storage::VecEpochEvents, simple_event::SimpleEvent,
         traits::RelevantEventSelector,
     },
-    mechanisms::{NoiseScale, NormType},
+    mechanisms::{MechanismChoice, NoiseScale, NormType},
     queries::traits::{
         EpochReportRequest, QueryComputeResult, Report, ReportRequestUris,
     },
@@ -18,11 +18,42 @@ pub struct SimpleLastTouchHistogramRequest {
     pub epoch_end: usize,
     pub report_global_sensitivity: f64,
     pub query_global_sensitivity: f64,
-    pub requested_epsilon: f64,
+
+    /// Which mechanism to request noise from: Laplace (ε-DP, L1
+    /// sensitivity) or Gaussian (zCDP, L2 sensitivity). For this
+    /// single-bin report, L1 and L2 sensitivity coincide, so either choice
+    /// is calibrated against `query_global_sensitivity`.
+    pub mechanism: MechanismChoice,
+
+    /// Which relevant events across `epoch_start..=epoch_end` get
+    /// attributed. See [`SimpleAttributionLogic`].
+    pub attribution_logic: SimpleAttributionLogic,
+
+    /// Caps the total value attributed across `epoch_start..=epoch_end`.
+    /// When set, this is what `report_global_sensitivity()` returns (not
+    /// the `report_global_sensitivity` field above), since it's the true
+    /// upper bound on what a report can reveal and noise must be
+    /// calibrated against it. When `None`, behaves as before: the
+    /// `report_global_sensitivity` field bounds a single touch, and no
+    /// cross-epoch cap is enforced.
+    pub max_attributable_value: Option<f64>,
+
     pub is_relevant_event: SimpleRelevantEventSelector,
     pub report_uris: ReportRequestUris<String>,
 }
 
+/// Which relevant events across a request's epoch window get attributed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SimpleAttributionLogic {
+    /// Attribute the full (capped) value to the single most recent
+    /// relevant event, as in the original hard-coded behavior.
+    LastTouch,
+
+    /// Attribute the capped total evenly across every epoch in the
+    /// request's window that has a relevant event.
+    EvenlyDistributed,
+}
+
 #[derive(Clone, Copy)]
 pub struct SimpleRelevantEventSelector {
     pub lambda: fn(&SimpleEvent) -> bool,
@@ -43,6 +74,10 @@ impl std::fmt::Debug for SimpleRelevantEventSelector {
     }
 }
 
+/// Single-bin report keyed by `event_key`, attributed according to the
+/// request's [`SimpleAttributionLogic`]. For a multi-bin report with a
+/// user-supplied bucket/value mapping, see
+/// [`GeneralHistogramRequest`](crate::queries::general_histogram::GeneralHistogramRequest).
 #[derive(Debug, Clone, Default)]
 pub struct SimpleLastTouchHistogramReport {
     // Value attributed to one bin or None if no attribution
@@ -67,6 +102,22 @@ impl EpochReportRequest for SimpleLastTouchHistogramRequest {
         self.report_uris.clone()
     }
 
+    fn hash_cache_identity(&self, hasher: &mut dyn std::hash::Hasher) {
+        use std::hash::Hash;
+        self.epoch_start.hash(hasher);
+        self.epoch_end.hash(hasher);
+        self.report_global_sensitivity.to_bits().hash(hasher);
+        self.query_global_sensitivity.to_bits().hash(hasher);
+        self.mechanism.hash_into(hasher);
+        self.attribution_logic.hash(hasher);
+        self.max_attributable_value.map(f64::to_bits).hash(hasher);
+        // `is_relevant_event.lambda` is a plain `fn` pointer, not a
+        // closure, so it hashes (and compares) by address like any other
+        // pointer -- no `finish_non_exhaustive`-style loss here.
+        self.is_relevant_event.lambda.hash(hasher);
+        self.report_uris.hash_into(hasher);
+    }
+
     fn epoch_ids(&self) -> Vec<Self::EpochId> {
         let range = self.epoch_start..=self.epoch_end;
         range.rev().collect()
@@ -80,45 +131,49 @@ impl EpochReportRequest for SimpleLastTouchHistogramRequest {
         &self,
         relevant_epochs_per_epoch: &HashMap<usize, Self::EpochEvents>,
     ) -> QueryComputeResult<Self::Uri, Self::Report> {
-        // Browse epochs in the order given by `epoch_ids, most recent
+        // Browse epochs in the order given by `epoch_ids`, most recent
         // epoch first. Within each epoch, we assume that events are
-        // stored in the order that they occured
-        for epoch_id in self.epoch_ids() {
-            if let Some(relevant_events) =
-                relevant_epochs_per_epoch.get(&epoch_id)
-            {
-                if let Some(last_impression) = relevant_events.last() {
-                    // `last_impression` is the most recent relevant impression
-                    // from the most recent non-empty epoch.
-                    let event_key = last_impression.event_key;
-                    let attributed_value = self.report_global_sensitivity;
-
-                    // Just use event_key as the bucket key.
-                    // See `ara_histogram` for a more general impression_key ->
-                    // bucket_key mapping.
-                    return QueryComputeResult::new(
-                        HashMap::new(),
-                        HashMap::from([(
-                            self.report_uris
-                                .querier_uris
-                                .first()
-                                .unwrap()
-                                .clone(),
-                            SimpleLastTouchHistogramReport {
-                                bin_value: Some((event_key, attributed_value)),
-                            },
-                        )]),
-                    );
-                }
+        // stored in the order that they occured.
+        let matched_epochs: Vec<(usize, usize)> = self
+            .epoch_ids()
+            .into_iter()
+            .filter_map(|epoch_id| {
+                relevant_epochs_per_epoch
+                    .get(&epoch_id)
+                    .and_then(|events| events.last())
+                    .map(|event| (epoch_id, event.event_key.as_u64() as usize))
+            })
+            .collect();
+
+        let cap =
+            self.max_attributable_value.unwrap_or(self.report_global_sensitivity);
+
+        let bin_value = match self.attribution_logic {
+            SimpleAttributionLogic::LastTouch => {
+                // `matched_epochs[0]` is the most recent relevant impression
+                // from the most recent non-empty epoch.
+                matched_epochs.first().map(|&(_, event_key)| {
+                    (event_key, self.report_global_sensitivity.min(cap))
+                })
             }
-        }
+            SimpleAttributionLogic::EvenlyDistributed => {
+                // Every matched epoch gets an equal share of the cap, but
+                // since this report type has only one bin (see
+                // `ara_histogram` for a more general impression_key ->
+                // bucket_key mapping), the whole (capped) total is reported
+                // under the most recently matched event's key: the per-epoch
+                // shares always sum to exactly `cap`.
+                matched_epochs
+                    .first()
+                    .map(|&(_, event_key)| (event_key, cap))
+            }
+        };
 
-        // No impressions were found so we return a report with a None bucket.
         QueryComputeResult::new(
             HashMap::new(),
             HashMap::from([(
                 self.report_uris.querier_uris.first().unwrap().clone(),
-                SimpleLastTouchHistogramReport { bin_value: None },
+                SimpleLastTouchHistogramReport { bin_value },
             )]),
         )
     }
@@ -148,12 +203,10 @@ impl EpochReportRequest for SimpleLastTouchHistogramRequest {
     }
 
     fn report_global_sensitivity(&self) -> f64 {
-        self.report_global_sensitivity
+        self.max_attributable_value.unwrap_or(self.report_global_sensitivity)
     }
 
     fn noise_scale(&self) -> NoiseScale {
-        NoiseScale::Laplace(
-            self.query_global_sensitivity / self.requested_epsilon,
-        )
+        self.mechanism.noise_scale(self.query_global_sensitivity)
     }
 }
@@ -1,7 +1,7 @@
 use std::{
     collections::HashMap,
     fmt::Debug,
-    hash::Hash,
+    hash::{Hash, Hasher},
 };
 
 use crate::{
@@ -9,6 +9,7 @@ use crate::{
     mechanisms::{NoiseScale, NormType},
 };
 
+#[derive(Debug, Clone)]
 pub struct QueryComputeResult<U, R> {
     pub bucket_uri_map: HashMap<usize, U>,
     pub uri_report_map: HashMap<U, R>,
@@ -43,6 +44,20 @@ pub struct ReportRequestUris<U> {
     pub querier_uris: Vec<U>,
 }
 
+impl<U: Hash> ReportRequestUris<U> {
+    /// Hashes every URI list, for implementors of
+    /// [`EpochReportRequest::hash_cache_identity`] that include
+    /// `report_uris` in their cache identity (any report genuinely depends
+    /// on its querier/source/intermediary URIs, so this is normally all of
+    /// them).
+    pub fn hash_into(&self, hasher: &mut dyn Hasher) {
+        self.trigger_uri.hash(&mut hasher);
+        self.source_uris.hash(&mut hasher);
+        self.intermediary_uris.hash(&mut hasher);
+        self.querier_uris.hash(&mut hasher);
+    }
+}
+
 /// Trait for report types returned by a device (in plaintext). Must implement a
 /// default variant for null reports, so devices with errors or no budget
 /// left are still sending something (and are thus indistinguishable from other
@@ -62,6 +77,18 @@ pub trait EpochReportRequest: Debug {
 
     fn report_uris(&self) -> ReportRequestUris<Self::Uri>;
 
+    /// Feeds every field that determines what `compute_report` would
+    /// produce into `hasher`, for use as a cache-key ingredient (see
+    /// [`query_compute_cache_key`](crate::pds::query_compute_cache::query_compute_cache_key)).
+    ///
+    /// Deliberately *not* derived from `Debug`: a `Debug` impl is free to
+    /// omit fields via `finish_non_exhaustive()` for fields that aren't
+    /// ergonomic to print (e.g. a `Box<dyn Fn>` bucket mapper), which would
+    /// silently collide two requests that differ only in that field. Each
+    /// implementor must hash its *complete* identity here, including any
+    /// field its `Debug` impl leaves out.
+    fn hash_cache_identity(&self, hasher: &mut dyn Hasher);
+
     /// Returns the list of requested epoch IDs, in the order the attribution
     /// should run.
     fn epoch_ids(&self) -> Vec<Self::EpochId>;
@@ -97,6 +124,14 @@ pub trait EpochReportRequest: Debug {
 
     /// Retrieves the scale of the noise that will be added by the aggregator.
     fn noise_scale(&self) -> NoiseScale;
+
+    /// Per-request override that always disables the cross-report budget
+    /// optimization (see `OptimizationPolicy` in `pds::core`) for this
+    /// request, regardless of the configured policy's thresholds. Defaults
+    /// to `false`.
+    fn opts_out_of_cross_report_optimization(&self) -> bool {
+        false
+    }
 }
 
 /// Type for passive privacy loss accounting. Uniform over all epochs for now.
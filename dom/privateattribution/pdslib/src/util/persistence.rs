@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+/// Read side of a key/value durable backend, e.g. an embedded KV store or a
+/// SQL table keyed by a single column. Kept separate from [`Writable`] since
+/// a backend that's only ever written through a cache (nothing reads it back
+/// within the same process) doesn't need to implement reads at all.
+pub trait Readable<K, V> {
+    type Error;
+
+    /// Reads `key` from the durable store. `None` means the key has never
+    /// been written, same convention as `FilterStorage::get_filter`.
+    fn read(&self, key: &K) -> Result<Option<V>, Self::Error>;
+}
+
+/// Write side of a key/value durable backend, fronted by an in-memory cache
+/// in callers like [`WriteThroughFilterStorage`](crate::budget::write_through_filter_storage::WriteThroughFilterStorage).
+pub trait Writable<K, V> {
+    type Error;
+
+    /// Writes `key` = `value` to the durable store.
+    fn write(&mut self, key: K, value: V) -> Result<(), Self::Error>;
+
+    /// Deletes `key` from the durable store, if present.
+    fn delete(&mut self, key: &K) -> Result<(), Self::Error>;
+
+    /// Writes every entry in `values` to the durable store. The default
+    /// implementation loops over `write`, i.e. one round trip per key;
+    /// backends that can batch (e.g. a single SQL transaction) should
+    /// override this, since a single `compute_report` can deduct budget
+    /// across many filters at once.
+    fn extend(
+        &mut self,
+        values: HashMap<K, V>,
+    ) -> Result<(), Self::Error> {
+        for (key, value) in values {
+            self.write(key, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Decides what a write-through cache does with an entry once it's been
+/// flushed to the durable store behind it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheUpdatePolicy {
+    /// Keep the entry cached with the value just written (the common case:
+    /// the value is still current, so there's no reason to fault it back in
+    /// from the durable store on the next read).
+    Overwrite,
+
+    /// Evict the entry from the cache after flushing, e.g. because the
+    /// caller knows it won't be read again soon and would rather free the
+    /// memory than keep it warm.
+    Remove,
+}
+
+/// In-memory [`Readable`]/[`Writable`] backend, for tests and for callers
+/// that want the write-through cache plumbing without a real durable store.
+/// Never fails, so `Error = Infallible`.
+///
+/// `Default` is implemented by hand rather than derived, since `derive`
+/// would require `K: Default, V: Default`, which neither a filter id nor a
+/// filter generally implements.
+#[derive(Debug)]
+pub struct InMemoryKv<K, V> {
+    entries: HashMap<K, V>,
+}
+
+impl<K, V> Default for InMemoryKv<K, V> {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+}
+
+impl<K: std::hash::Hash + Eq + Clone, V: Clone> Readable<K, V>
+    for InMemoryKv<K, V>
+{
+    type Error = std::convert::Infallible;
+
+    fn read(&self, key: &K) -> Result<Option<V>, Self::Error> {
+        Ok(self.entries.get(key).cloned())
+    }
+}
+
+impl<K: std::hash::Hash + Eq + Clone, V> Writable<K, V> for InMemoryKv<K, V> {
+    type Error = std::convert::Infallible;
+
+    fn write(&mut self, key: K, value: V) -> Result<(), Self::Error> {
+        self.entries.insert(key, value);
+        Ok(())
+    }
+
+    fn delete(&mut self, key: &K) -> Result<(), Self::Error> {
+        self.entries.remove(key);
+        Ok(())
+    }
+}
@@ -10,10 +10,12 @@ use pdslib::{
         hashmap_event_storage::HashMapEventStorage, simple_event::SimpleEvent,
         traits::EventUris,
     },
+    mechanisms::MechanismChoice,
     pds::epoch_pds::{EpochPrivateDataService, StaticCapacities},
     queries::{
         simple_last_touch_histogram::{
-            SimpleLastTouchHistogramRequest, SimpleRelevantEventSelector,
+            SimpleAttributionLogic, SimpleLastTouchHistogramRequest,
+            SimpleRelevantEventSelector,
         },
         traits::ReportRequestUris,
     },
@@ -83,7 +85,9 @@ fn main() -> Result<(), anyhow::Error> {
         epoch_end: 4,
         report_global_sensitivity,
         query_global_sensitivity,
-        requested_epsilon,
+        mechanism: MechanismChoice::Laplace { requested_epsilon },
+        attribution_logic: SimpleAttributionLogic::LastTouch,
+        max_attributable_value: None,
         is_relevant_event,
         report_uris: sample_report_uris.clone(),
     };
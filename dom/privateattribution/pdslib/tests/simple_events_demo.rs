@@ -11,10 +11,12 @@ use pdslib::{
         hashmap_event_storage::HashMapEventStorage, simple_event::SimpleEvent,
         traits::EventUris,
     },
+    mechanisms::MechanismChoice,
     pds::epoch_pds::{EpochPrivateDataService, StaticCapacities},
     queries::{
         simple_last_touch_histogram::{
-            SimpleLastTouchHistogramRequest, SimpleRelevantEventSelector,
+            SimpleAttributionLogic, SimpleLastTouchHistogramRequest,
+            SimpleRelevantEventSelector,
         },
         traits::ReportRequestUris,
     },
@@ -86,7 +88,9 @@ fn main() -> Result<(), anyhow::Error> {
         epoch_end: 1,
         report_global_sensitivity: 3.0,
         query_global_sensitivity: 5.0,
-        requested_epsilon: 5.0,
+        mechanism: MechanismChoice::Laplace { requested_epsilon: 5.0 },
+        attribution_logic: SimpleAttributionLogic::LastTouch,
+        max_attributable_value: None,
         is_relevant_event: always_relevant_event_selector,
         report_uris: sample_report_uris.clone(),
     };
@@ -113,7 +117,9 @@ fn main() -> Result<(), anyhow::Error> {
                        * for
                        * epoch 1 is 0. */
         query_global_sensitivity: 5.0,
-        requested_epsilon: 5.0,
+        mechanism: MechanismChoice::Laplace { requested_epsilon: 5.0 },
+        attribution_logic: SimpleAttributionLogic::LastTouch,
+        max_attributable_value: None,
         is_relevant_event: always_relevant_event_selector,
         report_uris: sample_report_uris.clone(),
     };
@@ -135,7 +141,9 @@ fn main() -> Result<(), anyhow::Error> {
         epoch_end: 2,
         report_global_sensitivity: 3.0,
         query_global_sensitivity: 5.0,
-        requested_epsilon: 5.0,
+        mechanism: MechanismChoice::Laplace { requested_epsilon: 5.0 },
+        attribution_logic: SimpleAttributionLogic::LastTouch,
+        max_attributable_value: None,
         is_relevant_event: always_relevant_event_selector,
         report_uris: sample_report_uris.clone(),
     };
@@ -156,7 +164,9 @@ fn main() -> Result<(), anyhow::Error> {
         epoch_end: 3,   // Epoch 3 not created yet.
         report_global_sensitivity: 0.0,
         query_global_sensitivity: 5.0,
-        requested_epsilon: 5.0,
+        mechanism: MechanismChoice::Laplace { requested_epsilon: 5.0 },
+        attribution_logic: SimpleAttributionLogic::LastTouch,
+        max_attributable_value: None,
         is_relevant_event: always_relevant_event_selector,
         report_uris: sample_report_uris.clone(),
     };
@@ -177,7 +187,9 @@ fn main() -> Result<(), anyhow::Error> {
         epoch_end: 3,
         report_global_sensitivity: 4.0,
         query_global_sensitivity: 5.0,
-        requested_epsilon: 5.0,
+        mechanism: MechanismChoice::Laplace { requested_epsilon: 5.0 },
+        attribution_logic: SimpleAttributionLogic::LastTouch,
+        max_attributable_value: None,
         is_relevant_event: always_relevant_event_selector,
         report_uris: sample_report_uris.clone(),
     };
@@ -199,7 +211,9 @@ fn main() -> Result<(), anyhow::Error> {
         epoch_end: 3,
         report_global_sensitivity: 3.0,
         query_global_sensitivity: 5.0,
-        requested_epsilon: 5.0,
+        mechanism: MechanismChoice::Laplace { requested_epsilon: 5.0 },
+        attribution_logic: SimpleAttributionLogic::LastTouch,
+        max_attributable_value: None,
         is_relevant_event: always_relevant_event_selector,
         report_uris: sample_report_uris.clone(),
     };
@@ -220,7 +234,9 @@ fn main() -> Result<(), anyhow::Error> {
         epoch_end: 3,
         report_global_sensitivity: 3.0,
         query_global_sensitivity: 5.0,
-        requested_epsilon: 5.0,
+        mechanism: MechanismChoice::Laplace { requested_epsilon: 5.0 },
+        attribution_logic: SimpleAttributionLogic::LastTouch,
+        max_attributable_value: None,
         is_relevant_event: SimpleRelevantEventSelector {
             lambda: |e: &SimpleEvent| e.event_key == 1,
         },
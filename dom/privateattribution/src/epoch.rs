@@ -4,12 +4,47 @@ use std::time::{SystemTime, UNIX_EPOCH};
 // all timestamps are in milliseconds, to correspond with
 // JS's Date.now()
 
-// note: Date.now() might have anti-fingerprinting that
-// rounds to the nearest 2ms. Should we have it too?
-
 pub const DAY_IN_MILLI: u64 = 1000 * 60 * 60 * 24;
 pub const EPOCH_DURATION: Duration = Duration::from_millis(7 * DAY_IN_MILLI);
 
+/// Configures how timestamps are bucketed into epochs: the epoch length, an
+/// anchor so epoch boundaries need not fall on the Unix origin, and a
+/// timestamp precision applied uniformly before bucketing (e.g. to match
+/// `Date.now()`'s anti-fingerprinting rounding, which rounds to the nearest
+/// couple of milliseconds). Passed explicitly to every conversion so a
+/// single request observes one consistent clock and one rounding rule,
+/// rather than baking either into a constant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EpochSchedule {
+    pub epoch_duration: Duration,
+    /// Timestamp (in milliseconds since the Unix epoch) of the start of
+    /// epoch 0.
+    pub anchor_ms: u64,
+    /// Timestamps are rounded down to the nearest multiple of this many
+    /// milliseconds before bucketing. `1` (or `0`) disables rounding.
+    pub precision_ms: u64,
+}
+
+impl Default for EpochSchedule {
+    fn default() -> Self {
+        Self {
+            epoch_duration: EPOCH_DURATION,
+            anchor_ms: 0,
+            precision_ms: 1,
+        }
+    }
+}
+
+impl EpochSchedule {
+    /// Rounds `timestamp` down to the nearest multiple of `precision_ms`.
+    pub fn round_timestamp(&self, timestamp: u64) -> u64 {
+        if self.precision_ms <= 1 {
+            return timestamp;
+        }
+        (timestamp / self.precision_ms) * self.precision_ms
+    }
+}
+
 pub fn timestamp_now() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -17,23 +52,20 @@ pub fn timestamp_now() -> u64 {
         .as_millis() as u64
 }
 
-pub fn timestamp_to_epoch(timestamp: u64) -> usize {
-    (timestamp / EPOCH_DURATION.as_millis() as u64) as usize
+pub fn timestamp_to_epoch(timestamp: u64, schedule: &EpochSchedule) -> usize {
+    let rounded = schedule.round_timestamp(timestamp);
+    let elapsed_since_anchor = rounded.saturating_sub(schedule.anchor_ms);
+    (elapsed_since_anchor / schedule.epoch_duration.as_millis() as u64) as usize
 }
 
-pub fn epoch_now() -> usize {
-    timestamp_to_epoch(timestamp_now())
+pub fn epoch_now(now: u64, schedule: &EpochSchedule) -> usize {
+    timestamp_to_epoch(now, schedule)
 }
 
-pub fn days_ago_to_epoch(days_ago: usize) -> usize {
-    // note: should Date::now() be passed in as an argument,
-    // to ensure the same time is used for all calculations?
-    // (that's how FF did it)
-    let now = timestamp_now();
-
+pub fn days_ago_to_epoch(days_ago: usize, now: u64, schedule: &EpochSchedule) -> usize {
     let days_ago = days_ago as u64;
     let days_ago_milli = days_ago * DAY_IN_MILLI;
-    let target_time = now - days_ago_milli;
+    let target_time = now.saturating_sub(days_ago_milli);
 
-    timestamp_to_epoch(target_time)
+    timestamp_to_epoch(target_time, schedule)
 }
@@ -5,11 +5,12 @@ use nsstring::{nsCString, nsString};
 use pdslib::{
     budget::{
         pure_dp_filter::{PureDPBudget, PureDPBudgetFilter},
-        traits::FilterStorage,
+        traits::{FilterBudgetEntry, FilterStorage},
     },
     pds::quotas::{FilterId, StaticCapacities},
 };
 use storage::Conn;
+use thiserror::Error;
 use xpcom::{
     getter_addrefs,
     interfaces::{mozIStorageService, nsIFile, nsIProperties},
@@ -18,6 +19,49 @@ use xpcom::{
 
 use crate::uri::MozUri;
 
+/// Errors from `SqliteFilterStorage`, wrapping the underlying mozStorage
+/// failure together with enough context (the operation in progress, and the
+/// filter involved when there is one) that a caller can tell a transient
+/// SQLite lock from a missing row from real corruption, instead of every
+/// failure collapsing into one opaque `NS_ERROR_FAILURE`.
+#[derive(Error, Debug)]
+pub enum SqliteFilterStorageError {
+    /// The database couldn't be opened, or a query against it failed for a
+    /// reason unrelated to the data itself (e.g. the connection dropped, or
+    /// the database is locked by another writer).
+    #[error("storage unavailable during {operation}: {source}")]
+    StorageUnavailable {
+        operation: &'static str,
+        #[source]
+        source: anyhow::Error,
+    },
+
+    /// A row was expected for `filter_id` (during this `operation`) but was
+    /// missing.
+    #[error("filter not found during {operation}: {filter_id:?}")]
+    FilterNotFound {
+        operation: &'static str,
+        filter_id: String,
+    },
+
+    /// A row for `filter_id` exists but its contents couldn't be
+    /// interpreted as a valid filter.
+    #[error("corrupt filter row for {filter_id:?}: {reason}")]
+    Corrupt { filter_id: String, reason: String },
+}
+
+impl SqliteFilterStorageError {
+    fn storage_unavailable(
+        operation: &'static str,
+        source: impl Into<anyhow::Error>,
+    ) -> Self {
+        Self::StorageUnavailable {
+            operation,
+            source: source.into(),
+        }
+    }
+}
+
 pub struct SqliteFilterStorage {
     capacities: StaticCapacities<FilterId<u64, MozUri>, PureDPBudget>,
     conn: Conn,
@@ -46,14 +90,19 @@ impl SqliteFilterStorage {
         Ok(db_file)
     }
 
-    pub fn clear_db(&self) -> Result<(), anyhow::Error> {
+    pub fn clear_db(&self) -> Result<(), SqliteFilterStorageError> {
         trace!("Clearing filters database");
+        const OP: &str = "clear_db";
 
         let query = "DELETE FROM filter";
         trace!("Executing query: {query}");
 
-        let mut stmt = self.conn.prepare(query)?;
-        stmt.execute()?;
+        let mut stmt = self
+            .conn
+            .prepare(query)
+            .map_err(|e| SqliteFilterStorageError::storage_unavailable(OP, e))?;
+        stmt.execute()
+            .map_err(|e| SqliteFilterStorageError::storage_unavailable(OP, e))?;
         trace!("Database cleared successfully");
 
         Ok(())
@@ -65,23 +114,31 @@ impl FilterStorage for SqliteFilterStorage {
     type Budget = PureDPBudget;
     type Filter = PureDPBudgetFilter;
     type Capacities = StaticCapacities<Self::FilterId, Self::Budget>;
-    type Error = anyhow::Error;
+    type Error = SqliteFilterStorageError;
 
     fn new(capacities: Self::Capacities) -> Result<Self, Self::Error>
     where
         Self: Sized,
     {
         trace!("SqliteFilterStorage::new");
+        const OP: &str = "new";
 
         let storage =
-            xpcom::get_service::<mozIStorageService>(c"@mozilla.org/storage/service;1").unwrap();
+            xpcom::get_service::<mozIStorageService>(c"@mozilla.org/storage/service;1")
+                .ok_or_else(|| {
+                    SqliteFilterStorageError::storage_unavailable(
+                        OP,
+                        anyhow::anyhow!("mozIStorageService unavailable"),
+                    )
+                })?;
 
-        let db_file = Self::db_file()?;
+        let db_file = Self::db_file()
+            .map_err(|e| SqliteFilterStorageError::storage_unavailable(OP, e))?;
 
         let conn = getter_addrefs(|p| unsafe {
             storage.OpenUnsharedDatabase(db_file.deref(), mozIStorageService::CONNECTION_DEFAULT, p)
         })
-        .unwrap();
+        .map_err(|e| SqliteFilterStorageError::storage_unavailable(OP, e))?;
         let conn = Conn::wrap(conn);
         trace!("Opened unshared database and got connection");
 
@@ -95,7 +152,9 @@ impl FilterStorage for SqliteFilterStorage {
             )";
         trace!("Creating filter table with query: {query}");
 
-        this.conn.exec(query)?;
+        this.conn
+            .exec(query)
+            .map_err(|e| SqliteFilterStorageError::storage_unavailable(OP, e))?;
         trace!("Filter table created successfully");
 
         Ok(this)
@@ -111,21 +170,39 @@ impl FilterStorage for SqliteFilterStorage {
     ) -> Result<Option<Self::Filter>, Self::Error> {
         let filter_id_str = nsCString::from(format!("{filter_id:?}"));
         trace!("SqliteFilterStorage::get_filter(filter_id={filter_id_str})");
+        const OP: &str = "get_filter";
 
         let query = "SELECT budget, capacity FROM filter WHERE id = :id";
         trace!("Getting filter with query: {query}\nfilter_id: {filter_id_str}");
 
-        let mut stmt = self.conn.prepare(query)?;
-        stmt.bind_by_name("id", filter_id_str.clone())?;
-
-        let Some(row) = stmt.step()? else {
+        let mut stmt = self
+            .conn
+            .prepare(query)
+            .map_err(|e| SqliteFilterStorageError::storage_unavailable(OP, e))?;
+        stmt.bind_by_name("id", filter_id_str.clone())
+            .map_err(|e| SqliteFilterStorageError::storage_unavailable(OP, e))?;
+
+        let Some(row) = stmt
+            .step()
+            .map_err(|e| SqliteFilterStorageError::storage_unavailable(OP, e))?
+        else {
             trace!("Filter ID not present in the database: {filter_id_str}");
             return Ok(None);
         };
 
-        let budget_value: f64 = row.get_by_name("budget")?;
+        let budget_value: f64 = row.get_by_name("budget").map_err(|_| {
+            SqliteFilterStorageError::Corrupt {
+                filter_id: filter_id_str.to_string(),
+                reason: "missing or non-numeric `budget` column".to_string(),
+            }
+        })?;
         let budget = Self::Budget::from(budget_value);
-        let capacity_value: Option<f64> = row.get_by_name("capacity")?;
+        let capacity_value: Option<f64> = row.get_by_name("capacity").map_err(|_| {
+            SqliteFilterStorageError::Corrupt {
+                filter_id: filter_id_str.to_string(),
+                reason: "non-numeric `capacity` column".to_string(),
+            }
+        })?;
         let capacity = capacity_value.map(Self::Budget::from);
 
         trace!("Filter retrieved successfully: {filter_id_str}, budget: {budget_value}, capacity: {capacity_value:?}");
@@ -144,6 +221,7 @@ impl FilterStorage for SqliteFilterStorage {
     ) -> Result<(), Self::Error> {
         let filter_id_str = nsCString::from(format!("{filter_id:?}"));
         trace!("SqliteFilterStorage::set_filter(filter_id={filter_id_str})");
+        const OP: &str = "set_filter";
 
         let budget_value: f64 = filter.consumed;
         let capacity_value: Option<f64> = filter.capacity.map(|c| c.into());
@@ -153,14 +231,72 @@ impl FilterStorage for SqliteFilterStorage {
         trace!("Setting filter with query: {query}\nfilter_id: {filter_id_str}\nbudget: {budget_value}\ncapacity: {capacity_value:?}");
 
         {
-            let mut stmt = self.conn.prepare(query)?;
-            stmt.bind_by_name("id", filter_id_str.clone())?;
-            stmt.bind_by_name("budget", budget_value)?;
-            stmt.bind_by_name("capacity", capacity_value)?;
-            stmt.execute()?;
+            let mut stmt = self
+                .conn
+                .prepare(query)
+                .map_err(|e| SqliteFilterStorageError::storage_unavailable(OP, e))?;
+            stmt.bind_by_name("id", filter_id_str.clone())
+                .map_err(|e| SqliteFilterStorageError::storage_unavailable(OP, e))?;
+            stmt.bind_by_name("budget", budget_value)
+                .map_err(|e| SqliteFilterStorageError::storage_unavailable(OP, e))?;
+            stmt.bind_by_name("capacity", capacity_value)
+                .map_err(|e| SqliteFilterStorageError::storage_unavailable(OP, e))?;
+            stmt.execute()
+                .map_err(|e| SqliteFilterStorageError::storage_unavailable(OP, e))?;
         }
 
         trace!("Filter set operation completed for filter_id: {filter_id_str}");
         Ok(())
     }
+
+    fn all_budgets(&mut self) -> Result<Vec<FilterBudgetEntry>, Self::Error> {
+        trace!("SqliteFilterStorage::all_budgets");
+        const OP: &str = "all_budgets";
+
+        let query = "SELECT id, budget, capacity FROM filter";
+        trace!("Enumerating filters with query: {query}");
+
+        let mut stmt = self
+            .conn
+            .prepare(query)
+            .map_err(|e| SqliteFilterStorageError::storage_unavailable(OP, e))?;
+
+        let mut entries = Vec::new();
+        while let Some(row) = stmt
+            .step()
+            .map_err(|e| SqliteFilterStorageError::storage_unavailable(OP, e))?
+        {
+            let filter_id: String = row.get_by_name("id").map_err(|_| {
+                SqliteFilterStorageError::Corrupt {
+                    filter_id: "<unknown>".to_string(),
+                    reason: "missing or non-text `id` column".to_string(),
+                }
+            })?;
+
+            let budget_value: f64 = row.get_by_name("budget").map_err(|_| {
+                SqliteFilterStorageError::Corrupt {
+                    filter_id: filter_id.clone(),
+                    reason: "missing or non-numeric `budget` column".to_string(),
+                }
+            })?;
+            let capacity_value: Option<f64> = row.get_by_name("capacity").map_err(|_| {
+                SqliteFilterStorageError::Corrupt {
+                    filter_id: filter_id.clone(),
+                    reason: "non-numeric `capacity` column".to_string(),
+                }
+            })?;
+
+            let remaining = match capacity_value {
+                Some(capacity) => capacity - budget_value,
+                None => f64::INFINITY,
+            };
+            entries.push(FilterBudgetEntry {
+                filter_id,
+                remaining,
+            });
+        }
+
+        trace!("Enumerated {} filter(s)", entries.len());
+        Ok(entries)
+    }
 }
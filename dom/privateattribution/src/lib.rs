@@ -1,12 +1,18 @@
+pub mod epoch;
 pub mod filter_storage;
+pub mod metrics;
 pub mod uri;
 
 use std::{collections::HashMap, ops::DerefMut, sync::Mutex};
 
-use filter_storage::SqliteFilterStorage;
+use filter_storage::{SqliteFilterStorage, SqliteFilterStorageError};
 use libc::c_void;
-use log::info;
-use nserror::{nsresult, NS_ERROR_FAILURE, NS_OK};
+use log::{error, info};
+use metrics::PdslibMetrics;
+use nserror::{
+    nsresult, NS_ERROR_FAILURE, NS_ERROR_FILE_CORRUPTED_DATABASE, NS_ERROR_NOT_AVAILABLE,
+    NS_ERROR_NOT_FOUND, NS_OK,
+};
 use nsstring::{nsACString, nsCString};
 use pdslib::{
     budget::{pure_dp_filter::PureDPBudget, traits::FilterStorage},
@@ -30,19 +36,34 @@ use xpcom::{
 #[xpcom(implement(nsIPrivateAttributionPdslibService), atomic)]
 struct PdslibService {
     pdslib: Mutex<PpaPdsCore<SqliteFilterStorage, MozUri>>,
+    metrics: PdslibMetrics,
+}
+
+/// Maps a [`SqliteFilterStorageError`] to a distinguishable `nsresult`,
+/// logging the full error (with its storage-level context) so diagnostic
+/// detail isn't lost even though XPCOM callers only see a status code.
+fn storage_error_to_nsresult(err: &SqliteFilterStorageError) -> nsresult {
+    error!("pdslib storage error: {err}");
+    match err {
+        SqliteFilterStorageError::StorageUnavailable { .. } => NS_ERROR_NOT_AVAILABLE,
+        SqliteFilterStorageError::FilterNotFound { .. } => NS_ERROR_NOT_FOUND,
+        SqliteFilterStorageError::Corrupt { .. } => NS_ERROR_FILE_CORRUPTED_DATABASE,
+    }
 }
 
 #[allow(non_snake_case)]
 impl PdslibService {
-    fn new() -> Result<RefPtr<Self>, ()> {
+    fn new() -> Result<RefPtr<Self>, nsresult> {
         info!("PdslibService::new");
 
         let capacities = Self::capacities();
-        let filters = SqliteFilterStorage::new(capacities).unwrap();
+        let filters =
+            SqliteFilterStorage::new(capacities).map_err(|e| storage_error_to_nsresult(&e))?;
         let pdslib = PpaPdsCore::new(filters);
 
         let this = Self::allocate(InitPdslibService {
             pdslib: Mutex::new(pdslib),
+            metrics: PdslibMetrics::new(),
         });
         Ok(this)
     }
@@ -82,6 +103,7 @@ impl PdslibService {
             requested_epsilon: get_attr(request, JsPpaHistogramRequest::GetRequestedEpsilon)?,
             histogram_size,
         };
+        self.metrics.record_requested_epsilon(config.requested_epsilon);
 
         let trigger_uri = get_attr_str(request, JsPpaHistogramRequest::GetTriggerHost)?;
         let uris = ReportRequestUris {
@@ -115,6 +137,14 @@ impl PdslibService {
         let report = pdslib.compute_report(&request, relevant_events).unwrap();
         let report = &report[&trigger_uri];
 
+        self.metrics.record_report_computed();
+        if report.filtered_report.early_stop {
+            self.metrics.record_report_capped();
+        }
+        if !report.oob_filters.is_empty() {
+            self.metrics.record_budget_denied();
+        }
+
         // create histogram from report
         let mut histogram = thin_vec![0.0; histogram_size as usize];
         for (bin, value) in &report.filtered_report.bin_values {
@@ -157,12 +187,52 @@ impl PdslibService {
 
         let mut pdslib = self.pdslib.lock().unwrap();
 
-        let budget = pdslib.filter_storage.remaining_budget(&filter_id).unwrap();
+        let budget = pdslib
+            .filter_storage
+            .remaining_budget(&filter_id)
+            .map_err(|e| storage_error_to_nsresult(&e))?;
+        self.metrics.record_filter_budget(&filter_id, budget);
 
         log::info!("Budget for filter {filter_id:?}: {budget}");
         return Ok(budget);
     }
 
+    xpcom_method!(
+        export_metrics => ExportMetrics() -> nsACString
+    );
+
+    fn export_metrics(&self) -> Result<nsCString, nsresult> {
+        log::info!("exportMetrics()");
+        Ok(nsCString::from(self.metrics.export()))
+    }
+
+    xpcom_method!(
+        get_all_budgets => GetAllBudgets() -> ThinVec<nsCString>
+    );
+
+    /// Enumerates every filter currently tracked, with its id and remaining
+    /// budget, in one call, so the front end can render a per-site/per-epoch
+    /// budget dashboard without a `GetBudget` round trip per filter. Each
+    /// entry is formatted `"<filterId>=<remainingBudget>"`; there's no XPCOM
+    /// dictionary type for a filter-budget pair in this tree, so a single
+    /// `ThinVec<nsCString>` mirrors the string-based filter identification
+    /// `GetBudget` already uses rather than introducing one.
+    fn get_all_budgets(&self) -> Result<ThinVec<nsCString>, nsresult> {
+        log::info!("getAllBudgets()");
+
+        let mut pdslib = self.pdslib.lock().unwrap();
+        let entries = pdslib
+            .filter_storage
+            .all_budgets()
+            .map_err(|e| storage_error_to_nsresult(&e))?;
+
+        let budgets = entries
+            .into_iter()
+            .map(|entry| nsCString::from(format!("{}={}", entry.filter_id, entry.remaining)))
+            .collect();
+        Ok(budgets)
+    }
+
     xpcom_method!(
         clear_budgets => ClearBudgets()
     );
@@ -171,7 +241,10 @@ impl PdslibService {
         log::info!("clearBudgets()");
 
         let pdslib = self.pdslib.lock().unwrap();
-        pdslib.filter_storage.clear_db().unwrap();
+        pdslib
+            .filter_storage
+            .clear_db()
+            .map_err(|e| storage_error_to_nsresult(&e))?;
 
         log::info!("Successfully cleared budgets");
         Ok(())
@@ -240,7 +313,7 @@ pub unsafe extern "C" fn nsPrivateAttributionPdslibConstructor(
 
     let service = match PdslibService::new() {
         Ok(service) => service,
-        Err(_) => return NS_ERROR_FAILURE,
+        Err(rv) => return rv,
     };
 
     service.QueryInterface(iid, result)
@@ -0,0 +1,182 @@
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+/// Default histogram bucket upper bounds, the same defaults Prometheus
+/// client libraries ship for request-duration-style histograms. Reused here
+/// for `requested_epsilon`, since both are typically small positive numbers
+/// spread across a few orders of magnitude.
+pub const DEFAULT_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// A Prometheus-style cumulative histogram: a sorted set of bucket upper
+/// bounds (plus an implicit `+Inf` bucket), each with a running count of
+/// observations `<=` its bound, alongside a total count and sum. Lock-free:
+/// `observe` only does atomic increments, never takes a lock.
+pub struct Histogram {
+    /// Sorted bucket upper bounds, not including `+Inf`.
+    bounds: Vec<f64>,
+    /// Cumulative per-bucket counts, same length and order as `bounds`.
+    bucket_counts: Vec<AtomicU64>,
+    count: AtomicU64,
+    /// Bit-pattern of an f64 running sum; there's no `AtomicF64` in std, so
+    /// updates go through a compare_exchange retry loop.
+    sum_bits: AtomicU64,
+}
+
+impl Histogram {
+    pub fn new(mut bounds: Vec<f64>) -> Self {
+        bounds.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let bucket_counts = bounds.iter().map(|_| AtomicU64::new(0)).collect();
+        Self {
+            bounds,
+            bucket_counts,
+            count: AtomicU64::new(0),
+            sum_bits: AtomicU64::new(0.0f64.to_bits()),
+        }
+    }
+
+    /// Records `value`: increments every bucket whose bound is `>= value`
+    /// (cumulative semantics), plus the total count and sum.
+    pub fn observe(&self, value: f64) {
+        for (bound, bucket_count) in self.bounds.iter().zip(&self.bucket_counts) {
+            if value <= *bound {
+                bucket_count.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+
+        let mut current = self.sum_bits.load(Ordering::Relaxed);
+        loop {
+            let new_sum = f64::from_bits(current) + value;
+            match self.sum_bits.compare_exchange_weak(
+                current,
+                new_sum.to_bits(),
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(observed) => current = observed,
+            }
+        }
+    }
+
+    /// Appends this histogram's lines to `out`, in Prometheus text
+    /// exposition format, under metric name `name`.
+    fn export(&self, out: &mut String, name: &str) {
+        let mut cumulative = 0u64;
+        for (bound, bucket_count) in self.bounds.iter().zip(&self.bucket_counts) {
+            cumulative = bucket_count.load(Ordering::Relaxed);
+            let _ = writeln!(out, "{name}_bucket{{le=\"{bound}\"}} {cumulative}");
+        }
+        let total = self.count.load(Ordering::Relaxed);
+        let _ = writeln!(out, "{name}_bucket{{le=\"+Inf\"}} {total}");
+        let _ = cumulative;
+
+        let sum = f64::from_bits(self.sum_bits.load(Ordering::Relaxed));
+        let _ = writeln!(out, "{name}_sum {sum}");
+        let _ = writeln!(out, "{name}_count {total}");
+    }
+}
+
+/// Counters and histograms tracking how `PdslibService` is used in a
+/// running Firefox profile, exported as Prometheus text exposition format
+/// via `PdslibService::ExportMetrics`. All fields are safe to update from
+/// `&self`: counters are atomic, and `requested_epsilon` is a lock-free
+/// [`Histogram`].
+#[derive(Default)]
+pub struct PdslibMetrics {
+    reports_computed: AtomicU64,
+    /// Reports where `compute_histogram_report` hit the contribution cap
+    /// and stopped early (`HistogramReport::early_stop`).
+    reports_capped: AtomicU64,
+    /// Reports where at least one epoch was dropped for being out of
+    /// budget (`PdsReport::oob_filters` non-empty).
+    budget_denied: AtomicU64,
+    requested_epsilon: Histogram,
+    /// Last-observed remaining budget per filter (keyed by its `Debug`
+    /// string; `AggregatingMetricsSink` in pdslib keys by `Display`),
+    /// updated whenever `get_budget` reads a filter's budget. Lets operators watch
+    /// depletion in a dashboard without a JS round-trip per filter.
+    filter_budgets: Mutex<HashMap<String, f64>>,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self::new(DEFAULT_BUCKETS.to_vec())
+    }
+}
+
+impl PdslibMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_report_computed(&self) {
+        self.reports_computed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_report_capped(&self) {
+        self.reports_capped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_budget_denied(&self) {
+        self.budget_denied.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_requested_epsilon(&self, epsilon: f64) {
+        self.requested_epsilon.observe(epsilon);
+    }
+
+    pub fn record_filter_budget(&self, filter_id: impl std::fmt::Debug, remaining: f64) {
+        let mut filter_budgets = self.filter_budgets.lock().unwrap();
+        filter_budgets.insert(format!("{filter_id:?}"), remaining);
+    }
+
+    /// Renders every tracked metric as Prometheus text exposition format.
+    pub fn export(&self) -> String {
+        let mut out = String::new();
+
+        let _ = writeln!(out, "# TYPE pdslib_reports_computed_total counter");
+        let _ = writeln!(
+            out,
+            "pdslib_reports_computed_total {}",
+            self.reports_computed.load(Ordering::Relaxed)
+        );
+
+        let _ = writeln!(out, "# TYPE pdslib_reports_capped_total counter");
+        let _ = writeln!(
+            out,
+            "pdslib_reports_capped_total {}",
+            self.reports_capped.load(Ordering::Relaxed)
+        );
+
+        let _ = writeln!(out, "# TYPE pdslib_budget_denied_total counter");
+        let _ = writeln!(
+            out,
+            "pdslib_budget_denied_total {}",
+            self.budget_denied.load(Ordering::Relaxed)
+        );
+
+        let _ = writeln!(out, "# TYPE pdslib_requested_epsilon histogram");
+        self.requested_epsilon.export(&mut out, "pdslib_requested_epsilon");
+
+        let _ = writeln!(out, "# TYPE pdslib_filter_remaining_budget gauge");
+        let filter_budgets = self.filter_budgets.lock().unwrap();
+        for (filter_id, remaining) in filter_budgets.iter() {
+            let _ = writeln!(
+                out,
+                "pdslib_filter_remaining_budget{{filter_id=\"{filter_id}\"}} {remaining}"
+            );
+        }
+        drop(filter_budgets);
+
+        out
+    }
+}